@@ -0,0 +1,600 @@
+// A two pass 8080 assembler for the same mnemonics the decoder produces. The
+// first pass works out where every label is, the second writes the bytes.
+//
+// Supports labels, ORG, EQU, DB, DW, DS and END, with expressions made of
+// numbers (see `number`), characters like 'A', symbols, `$` for the current
+// address, + - * / % & | ^ ~ and HIGH/LOW.
+
+use decoder::decode;
+use instruction::{Instruction, Mnemonic, Operand};
+use number;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    // 1 based line number in the source.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {}
+
+// The assembled bytes, running from the lowest address anything was written
+// to up to the highest. Gaps left by ORG or DS are filled with zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    pub origin: u16,
+    pub bytes: Vec<u8>,
+}
+
+pub fn assemble(source: &str) -> Result<Assembly, AssembleError> {
+    let table = opcode_table();
+    let mut statements = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let statement = parse_line(text, &table).map_err(|message| AssembleError {
+            line: index + 1,
+            message,
+        })?;
+        let end = statement.directive == Directive::End;
+        statements.push((index + 1, statement));
+        if end {
+            break;
+        }
+    }
+
+    let mut symbols = HashMap::new();
+    pass(&statements, &table, &mut symbols, None)?;
+    let mut memory = Memory::new();
+    pass(&statements, &table, &mut symbols, Some(&mut memory))?;
+    Ok(memory.assembly())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Directive {
+    None,
+    Instruction(Mnemonic, Vec<String>),
+    Org(String),
+    Equ(String),
+    Db(Vec<String>),
+    Dw(Vec<String>),
+    Ds(String),
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statement {
+    label: Option<String>,
+    directive: Directive,
+}
+
+// Everything the decoder knows, minus the undocumented opcodes since those
+// would make some mnemonics ambiguous.
+fn opcode_table() -> Vec<Instruction> {
+    (0..=255u8)
+        .filter_map(|opcode| decode(&[opcode, 0, 0], 0).ok())
+        .filter(|instruction| !instruction.undocumented)
+        .collect()
+}
+
+fn parse_line(text: &str, table: &[Instruction]) -> Result<Statement, String> {
+    let text = strip_comment(text);
+    let mut rest = text.trim();
+    let mut label = None;
+
+    // A label either ends with a colon or starts right at the beginning of the line.
+    let first = first_word(rest);
+    if let Some(name) = first.strip_suffix(':') {
+        label = Some(name.to_uppercase());
+        rest = rest[first.len()..].trim_start();
+    } else if !first.is_empty()
+        && !is_keyword(first, table)
+        && (!text.starts_with(char::is_whitespace)
+            || is_equ(first_word(rest[first.len()..].trim())))
+    {
+        label = Some(first.to_uppercase());
+        rest = rest[first.len()..].trim_start();
+    }
+
+    let word = first_word(rest);
+    let operands = rest[word.len()..].trim();
+    let keyword = word.to_uppercase();
+    let directive = match &keyword as &str {
+        "" => Directive::None,
+        "ORG" => Directive::Org(operands.to_string()),
+        _ if is_equ(word) => Directive::Equ(operands.to_string()),
+        "DB" | "DEFB" | "DEFM" => Directive::Db(split_operands(operands)),
+        "DW" | "DEFW" => Directive::Dw(split_operands(operands)),
+        "DS" | "DEFS" => Directive::Ds(operands.to_string()),
+        "END" => Directive::End,
+        _ => match mnemonic(&keyword, table) {
+            Some(mnemonic) => Directive::Instruction(mnemonic, split_operands(operands)),
+            None => return Err(format!("Unknown instruction: {}", word)),
+        },
+    };
+    if let Directive::Equ(_) = directive {
+        if label.is_none() {
+            return Err("EQU needs a name".to_string());
+        }
+    }
+    Ok(Statement { label, directive })
+}
+
+fn is_equ(word: &str) -> bool {
+    matches!(&word.to_uppercase() as &str, "EQU" | "SET" | "=")
+}
+
+fn first_word(text: &str) -> &str {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    &text[..end]
+}
+
+fn is_keyword(word: &str, table: &[Instruction]) -> bool {
+    let word = word.to_uppercase();
+    match &word as &str {
+        "ORG" | "EQU" | "SET" | "DB" | "DEFB" | "DEFM" | "DW" | "DEFW" | "DS" | "DEFS" | "END" => {
+            true
+        }
+        _ => mnemonic(&word, table).is_some(),
+    }
+}
+
+fn mnemonic(word: &str, table: &[Instruction]) -> Option<Mnemonic> {
+    table
+        .iter()
+        .map(|instruction| instruction.mnemonic)
+        .find(|mnemonic| mnemonic.to_string() == word)
+}
+
+// Drops everything after a `;` that isn't inside quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, ';') => return &text[..index],
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    text
+}
+
+// Splits on commas, leaving alone any inside quotes or brackets.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut depth = 0;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, ',') if depth == 0 => {
+                operands.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() || !operands.is_empty() {
+        operands.push(current.trim().to_string());
+    }
+    operands
+}
+
+struct Memory {
+    bytes: Vec<u8>,
+    lowest: Option<usize>,
+    highest: usize,
+}
+
+impl Memory {
+    fn new() -> Memory {
+        Memory {
+            bytes: vec![0; 0x10000],
+            lowest: None,
+            highest: 0,
+        }
+    }
+
+    fn write(&mut self, address: u16, byte: u8) {
+        let address = address as usize;
+        self.bytes[address] = byte;
+        self.lowest = Some(self.lowest.map_or(address, |lowest| lowest.min(address)));
+        self.highest = self.highest.max(address);
+    }
+
+    fn assembly(self) -> Assembly {
+        match self.lowest {
+            Some(lowest) => Assembly {
+                origin: lowest as u16,
+                bytes: self.bytes[lowest..=self.highest].to_vec(),
+            },
+            None => Assembly {
+                origin: 0,
+                bytes: Vec::new(),
+            },
+        }
+    }
+}
+
+// Walks the program once. Without memory it's the first pass and only
+// records symbols, where undefined names are fine because they might be
+// defined further down. With memory it's the second pass and writes bytes.
+fn pass(
+    statements: &[(usize, Statement)],
+    table: &[Instruction],
+    symbols: &mut HashMap<String, i64>,
+    mut memory: Option<&mut Memory>,
+) -> Result<(), AssembleError> {
+    let final_pass = memory.is_some();
+    let mut address: u16 = 0;
+    for &(line, ref statement) in statements {
+        let error = |message: String| AssembleError { line, message };
+        // ORG and DS decide where everything after them goes, so they're
+        // worked out on the first pass, before later names are known.
+        let placement =
+            |directive: &str, message: String| match message.strip_prefix("Undefined symbol: ") {
+                Some(name) => error(format!(
+                    "{} can only use names defined above it, and {} isn't",
+                    directive, name
+                )),
+                None => error(message),
+            };
+        if let Some(ref label) = statement.label {
+            if let Directive::Equ(ref expression) = statement.directive {
+                match evaluate(expression, symbols, address) {
+                    Ok(value) => {
+                        symbols.insert(label.clone(), value);
+                    }
+                    Err(message) => {
+                        if final_pass {
+                            return Err(error(message));
+                        }
+                    }
+                }
+            } else {
+                if !final_pass && symbols.contains_key(label) {
+                    return Err(error(format!("{} is defined more than once", label)));
+                }
+                symbols.insert(label.clone(), i64::from(address));
+            }
+        }
+        let mut output = Vec::new();
+        match statement.directive {
+            Directive::None | Directive::Equ(_) | Directive::End => {}
+            Directive::Org(ref expression) => {
+                address = word(
+                    evaluate(expression, symbols, address)
+                        .map_err(|message| placement("ORG", message))?,
+                )
+                .map_err(&error)?;
+            }
+            Directive::Ds(ref expression) => {
+                let size = evaluate(expression, symbols, address)
+                    .map_err(|message| placement("DS", message))?;
+                address = address.wrapping_add(word(size).map_err(&error)?);
+            }
+            Directive::Db(ref items) => {
+                for item in items {
+                    match string_literal(item) {
+                        Some(text) => output.extend(text.bytes()),
+                        None => {
+                            let value = if final_pass {
+                                evaluate(item, symbols, address).map_err(&error)?
+                            } else {
+                                0
+                            };
+                            output.push(byte(value).map_err(&error)?);
+                        }
+                    }
+                }
+            }
+            Directive::Dw(ref items) => {
+                for item in items {
+                    let value = if final_pass {
+                        word(evaluate(item, symbols, address).map_err(&error)?).map_err(&error)?
+                    } else {
+                        0
+                    };
+                    output.push(value as u8);
+                    output.push((value >> 8) as u8);
+                }
+            }
+            Directive::Instruction(mnemonic, ref operands) => {
+                let values = if final_pass {
+                    Some(symbols as &HashMap<String, i64>)
+                } else {
+                    None
+                };
+                output = encode(mnemonic, operands, table, values, address).map_err(&error)?;
+            }
+        }
+        if let Some(ref mut memory) = memory {
+            for (offset, &value) in output.iter().enumerate() {
+                memory.write(address.wrapping_add(offset as u16), value);
+            }
+        }
+        address = address.wrapping_add(output.len() as u16);
+    }
+    Ok(())
+}
+
+// Picks the opcode whose register operands match and fills in the data bytes.
+// Without symbols it only needs to get the length right, so expressions are
+// left as zero.
+fn encode(
+    mnemonic: Mnemonic,
+    operands: &[String],
+    table: &[Instruction],
+    symbols: Option<&HashMap<String, i64>>,
+    address: u16,
+) -> Result<Vec<u8>, String> {
+    let candidates: Vec<&Instruction> = table
+        .iter()
+        .filter(|instruction| instruction.mnemonic == mnemonic)
+        .collect();
+    for candidate in &candidates {
+        if candidate.operands.len() != operands.len() {
+            continue;
+        }
+        let mut bytes = vec![candidate.opcode];
+        let mut matched = true;
+        for (expected, text) in candidate.operands.iter().zip(operands) {
+            // Register names can be any case, but expressions are evaluated
+            // as they are so 'a' stays lower case.
+            match *expected {
+                Operand::Reg(register) => {
+                    matched &= text.eq_ignore_ascii_case(&register.to_string())
+                }
+                Operand::Pair(pair) => matched &= text.eq_ignore_ascii_case(&pair.to_string()),
+                Operand::Vector(number) => {
+                    let value = match symbols {
+                        Some(symbols) => evaluate(text, symbols, address)?,
+                        None => evaluate(text, &HashMap::new(), address).unwrap_or(-1),
+                    };
+                    // Before symbols are known any RST will do for the length.
+                    matched &= value == i64::from(number) || (symbols.is_none() && number == 0);
+                }
+                Operand::Byte(_) | Operand::Port(_) => {
+                    let value = match symbols {
+                        Some(symbols) => evaluate(text, symbols, address)?,
+                        None => 0,
+                    };
+                    bytes.push(byte(value)?);
+                }
+                Operand::Word(_) | Operand::Address(_) => {
+                    let value = match symbols {
+                        Some(symbols) => word(evaluate(text, symbols, address)?)?,
+                        None => 0,
+                    };
+                    bytes.push(value as u8);
+                    bytes.push((value >> 8) as u8);
+                }
+            }
+        }
+        if matched {
+            return Ok(bytes);
+        }
+    }
+    Err(format!(
+        "Bad operands for {}: {}",
+        mnemonic,
+        operands.join(",")
+    ))
+}
+
+fn byte(value: i64) -> Result<u8, String> {
+    if (-128..=255).contains(&value) {
+        Ok(value as u8)
+    } else {
+        Err(format!("{} doesn't fit in a byte", value))
+    }
+}
+
+fn word(value: i64) -> Result<u16, String> {
+    if (-32768..=65535).contains(&value) {
+        Ok(value as u16)
+    } else {
+        Err(format!("{} doesn't fit in a word", value))
+    }
+}
+
+// Strings longer than one character in a DB, e.g. DB "INSERT COIN".
+// A single character is left for the expression parser so 'A'+80H works.
+fn string_literal(text: &str) -> Option<&str> {
+    let quote = text.chars().next()?;
+    if (quote == '"' || quote == '\'') && text.len() >= 2 && text.ends_with(quote) {
+        let inner = &text[1..text.len() - 1];
+        if inner.chars().count() != 1 {
+            return Some(inner);
+        }
+    }
+    None
+}
+
+fn evaluate(text: &str, symbols: &HashMap<String, i64>, address: u16) -> Result<i64, String> {
+    let mut parser = Expression {
+        chars: text.chars().collect(),
+        position: 0,
+        symbols,
+        address,
+    };
+    let value = parser.or()?;
+    parser.skip_spaces();
+    if parser.position < parser.chars.len() {
+        return Err(format!("Can't make sense of expression: {}", text));
+    }
+    Ok(value)
+}
+
+struct Expression<'a> {
+    chars: Vec<char>,
+    position: usize,
+    symbols: &'a HashMap<String, i64>,
+    address: u16,
+}
+
+impl<'a> Expression<'a> {
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+
+    // Looks past any spaces for the next character and takes it if it's `c`.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<i64, String> {
+        let mut value = self.xor()?;
+        while self.eat('|') {
+            value |= self.xor()?;
+        }
+        Ok(value)
+    }
+
+    fn xor(&mut self) -> Result<i64, String> {
+        let mut value = self.and()?;
+        while self.eat('^') {
+            value ^= self.and()?;
+        }
+        Ok(value)
+    }
+
+    fn and(&mut self) -> Result<i64, String> {
+        let mut value = self.sum()?;
+        while self.eat('&') {
+            value &= self.sum()?;
+        }
+        Ok(value)
+    }
+
+    fn sum(&mut self) -> Result<i64, String> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value = overflow(value.checked_add(self.product()?))?;
+            } else if self.eat('-') {
+                value = overflow(value.checked_sub(self.product()?))?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<i64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value = overflow(value.checked_mul(self.unary()?))?;
+            } else if self.eat('/') {
+                value = overflow(value.checked_div(self.divisor()?))?;
+            } else if self.eat('%') {
+                value = overflow(value.checked_rem(self.divisor()?))?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn divisor(&mut self) -> Result<i64, String> {
+        match self.unary()? {
+            0 => Err("Division by zero".to_string()),
+            divisor => Ok(divisor),
+        }
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if self.eat('-') {
+            return overflow(self.unary()?.checked_neg());
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        if self.eat('~') {
+            return Ok(!self.unary()?);
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<i64, String> {
+        self.skip_spaces();
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err("Missing value in expression".to_string()),
+        };
+        if c == '(' {
+            self.position += 1;
+            let value = self.or()?;
+            if !self.eat(')') {
+                return Err("Missing )".to_string());
+            }
+            return Ok(value);
+        }
+        if c == '\'' || c == '"' {
+            let value = self.chars.get(self.position + 1).cloned();
+            if self.chars.get(self.position + 2) != Some(&c) {
+                return Err("Character constants need closing quotes".to_string());
+            }
+            self.position += 3;
+            return Ok(i64::from(value.map_or(0, |value| value as u32)));
+        }
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '?' || c == '@')
+        {
+            self.position += 1;
+        }
+        let word: String = self.chars[start..self.position].iter().collect();
+        let upper = word.to_uppercase();
+        if word.is_empty() {
+            return Err(format!("Unexpected {} in expression", c));
+        }
+        if upper == "$" {
+            return Ok(i64::from(self.address));
+        }
+        if upper == "HIGH" {
+            return Ok((self.unary()? >> 8) & 0xff);
+        }
+        if upper == "LOW" {
+            return Ok(self.unary()? & 0xff);
+        }
+        if c.is_ascii_digit() || (c == '$' && word.len() > 1) {
+            return number::parse(&word).ok_or_else(|| format!("Bad number: {}", word));
+        }
+        match self.symbols.get(&upper) {
+            Some(&value) => Ok(value),
+            None => Err(format!("Undefined symbol: {}", word)),
+        }
+    }
+}
+
+// Anything too big for an i64 is far too big for an 8080 anyway.
+fn overflow(value: Option<i64>) -> Result<i64, String> {
+    value.ok_or_else(|| "Expression is too big".to_string())
+}
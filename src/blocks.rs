@@ -0,0 +1,231 @@
+// Basic blocks: runs of instructions that always execute together, from one
+// that something jumps to (or that follows a jump, call or return) up to the
+// next jump, call, return, RST or PCHL. Joined up by where each one can go
+// next they make a control flow graph, which shows the loops and branches in
+// a routine far better than the flat listing does, especially drawn with
+// Graphviz (see `write_dot`).
+
+use dispatch::{self, Target};
+use instruction::{Flow, Instruction};
+use labels::Labels;
+use listing::{self, DataFormat, Line};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{self, Write};
+use syntax::Syntax;
+
+// How a block gets to one of the blocks after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Edge {
+    // Runs on into the next block, including coming back from a call.
+    Next,
+    Jump,
+    // A conditional jump that was taken.
+    Branch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: u16,
+    pub instructions: Vec<Instruction>,
+    // Where it can go next, in address order.
+    pub successors: Vec<(u16, Edge)>,
+    // The routine the last instruction calls, if it's a CALL or RST.
+    pub call: Option<u16>,
+}
+
+impl Block {
+    pub fn last(&self) -> &Instruction {
+        self.instructions.last().unwrap()
+    }
+
+    // The address straight after the block.
+    pub fn end(&self) -> u16 {
+        self.last().next_address()
+    }
+}
+
+// Splits the code in the listing into blocks, keyed by their first address.
+// Data lines end a block too, since whatever comes before them can't run on.
+pub fn blocks(lines: &[Line]) -> BTreeMap<u16, Block> {
+    // The addresses in each pointer table, by where the table starts.
+    let mut pointers: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+    for line in lines {
+        if let Line::Data {
+            address,
+            ref bytes,
+            format: DataFormat::Pointers,
+            ..
+        } = *line
+        {
+            pointers.insert(address, listing::words(bytes));
+        }
+    }
+    let pchl_targets: BTreeMap<u16, Vec<u16>> = dispatch::pchl_targets(lines)
+        .into_iter()
+        .map(|(address, target)| {
+            let targets = match target {
+                Target::Address(target) => vec![target],
+                Target::Table(table) => table_entries(&pointers, table),
+            };
+            (address, targets)
+        })
+        .collect();
+    let targets: HashSet<u16> = lines
+        .iter()
+        .filter_map(|line| match *line {
+            Line::Instruction(ref instruction) => instruction.target(),
+            _ => None,
+        })
+        .chain(pchl_targets.values().flatten().cloned())
+        .collect();
+    let mut blocks = BTreeMap::new();
+    let mut current: Option<Block> = None;
+    for line in lines {
+        let instruction = match *line {
+            Line::Instruction(ref instruction) => instruction,
+            Line::Data { .. } => {
+                blocks.extend(current.take().map(|block| (block.start, block)));
+                continue;
+            }
+        };
+        // Something jumps into the middle, so the block ends here and runs on
+        // into a new one.
+        if targets.contains(&instruction.address) {
+            if let Some(mut block) = current.take() {
+                block.successors.push((instruction.address, Edge::Next));
+                blocks.insert(block.start, block);
+            }
+        }
+        let mut block = current.take().unwrap_or_else(|| Block {
+            start: instruction.address,
+            instructions: Vec::new(),
+            successors: Vec::new(),
+            call: None,
+        });
+        let next = instruction.next_address();
+        match instruction.flow() {
+            Flow::Next => {
+                block.instructions.push(instruction.clone());
+                current = Some(block);
+                continue;
+            }
+            Flow::Jump(target) => block.successors.push((target, Edge::Jump)),
+            Flow::ConditionalJump(target) => {
+                block.successors.push((target, Edge::Branch));
+                block.successors.push((next, Edge::Next));
+            }
+            Flow::Call(target) | Flow::ConditionalCall(target) => {
+                block.call = Some(target);
+                block.successors.push((next, Edge::Next));
+            }
+            Flow::ConditionalReturn => block.successors.push((next, Edge::Next)),
+            Flow::Return => {}
+            Flow::Indirect => {
+                for &target in pchl_targets.get(&instruction.address).into_iter().flatten() {
+                    block.successors.push((target, Edge::Jump));
+                }
+            }
+        }
+        block.instructions.push(instruction.clone());
+        block.successors.sort();
+        block.successors.dedup();
+        blocks.insert(block.start, block);
+    }
+    blocks.extend(current.map(|block| (block.start, block)));
+    blocks
+}
+
+// The blocks a routine is made of: everything reachable from its entry
+// without following a call. In address order.
+pub fn routine(blocks: &BTreeMap<u16, Block>, entry: u16) -> Vec<&Block> {
+    let mut found = BTreeSet::new();
+    let mut pending = vec![entry];
+    while let Some(address) = pending.pop() {
+        let block = match blocks.get(&address) {
+            Some(block) => block,
+            None => continue,
+        };
+        if found.insert(address) {
+            pending.extend(block.successors.iter().map(|&(to, _)| to));
+        }
+    }
+    found.iter().map(|address| &blocks[address]).collect()
+}
+
+// Writes a Graphviz graph with a box for each block in each of the routines,
+// grouped into a cluster per routine. A block shared by two routines (a
+// common exit, say) is drawn in both.
+pub fn write_dot<W: Write>(
+    out: &mut W,
+    blocks: &BTreeMap<u16, Block>,
+    entries: &[u16],
+    labels: &Labels,
+    syntax: &Syntax,
+) -> io::Result<()> {
+    let name = |address: u16| match labels.get(address) {
+        Some(name) => name.to_string(),
+        None => format!("{:04x}", address),
+    };
+    writeln!(out, "digraph cfg {{")?;
+    writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+    for &entry in entries {
+        let routine = routine(blocks, entry);
+        if routine.is_empty() {
+            continue;
+        }
+        let node = |address: u16| format!("\"{:04x}_{:04x}\"", entry, address);
+        writeln!(out)?;
+        writeln!(out, "    subgraph \"cluster_{:04x}\" {{", entry)?;
+        writeln!(out, "        label=\"{}\";", escape(&name(entry)))?;
+        for block in &routine {
+            let mut text = String::new();
+            if labels.get(block.start).is_some() {
+                text.push_str(&format!("{}:\\l", escape(&name(block.start))));
+            }
+            for instruction in &block.instructions {
+                text.push_str(&format!(
+                    "{:x}     {:02x}: {}\\l",
+                    instruction.address,
+                    instruction.opcode,
+                    escape(&syntax.instruction(instruction, Some(labels)))
+                ));
+            }
+            writeln!(out, "        {} [label=\"{}\"];", node(block.start), text)?;
+        }
+        for block in &routine {
+            for &(to, edge) in &block.successors {
+                if !blocks.contains_key(&to) {
+                    continue;
+                }
+                let style = match edge {
+                    Edge::Branch => " [label=\"taken\"]",
+                    Edge::Jump | Edge::Next => "",
+                };
+                writeln!(
+                    out,
+                    "        {} -> {}{};",
+                    node(block.start),
+                    node(to),
+                    style
+                )?;
+            }
+        }
+        writeln!(out, "    }}")?;
+    }
+    writeln!(out, "}}")
+}
+
+// The addresses in a jump table, which may be split over several lines.
+fn table_entries(pointers: &BTreeMap<u16, Vec<u16>>, start: u16) -> Vec<u16> {
+    let mut entries = Vec::new();
+    let mut address = start;
+    while let Some(words) = pointers.get(&address).filter(|words| !words.is_empty()) {
+        entries.extend(words);
+        address = address.wrapping_add(words.len() as u16 * 2);
+    }
+    entries
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
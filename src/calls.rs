@@ -0,0 +1,309 @@
+// The call graph: which routines there are, what each one calls, and what
+// that says about the program as a whole. A routine starts wherever something
+// calls (CALL, RST), wherever the program starts, and wherever a PCHL goes
+// that isn't reached any other way. It takes in every block it can get to
+// without a call, so loops and shared exits are part of it, and a jump into
+// the start of another routine is a tail call rather than more of this one.
+
+use blocks::{self, Block};
+use instruction::Flow;
+use labels::Labels;
+use listing::Line;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::io::{self, Write};
+
+// Why a routine counts as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reach {
+    // Where the program starts, or an interrupt vector.
+    Entry,
+    Call,
+    // Only ever reached by a PCHL.
+    Indirect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CallKind {
+    Call,
+    // A jump to the start of another routine, which returns for this one.
+    Tail,
+    // PCHL.
+    Indirect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Routine {
+    pub entry: u16,
+    pub reach: Reach,
+    // The start of each of its blocks, in order.
+    pub blocks: Vec<u16>,
+    // The routines it goes to, in order.
+    pub calls: Vec<(u16, CallKind)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    pub routines: BTreeMap<u16, Routine>,
+    // Where the program starts, which everything else should be reached from.
+    pub roots: Vec<u16>,
+}
+
+impl CallGraph {
+    pub fn new(blocks: &BTreeMap<u16, Block>, roots: &[u16]) -> CallGraph {
+        let roots: Vec<u16> = roots
+            .iter()
+            .cloned()
+            .filter(|root| blocks.contains_key(root))
+            .collect();
+        let mut reach: BTreeMap<u16, Reach> =
+            roots.iter().map(|&root| (root, Reach::Entry)).collect();
+        let mut jumped = HashSet::new();
+        let mut indirect = HashSet::new();
+        for block in blocks.values() {
+            if let Some(target) = block.call {
+                if blocks.contains_key(&target) {
+                    reach.entry(target).or_insert(Reach::Call);
+                }
+            }
+            let targets = block.successors.iter().map(|&(to, _)| to);
+            if block.last().flow() == Flow::Indirect {
+                indirect.extend(targets);
+            } else {
+                jumped.extend(targets);
+            }
+        }
+        for target in indirect {
+            if !jumped.contains(&target) && blocks.contains_key(&target) {
+                reach.entry(target).or_insert(Reach::Indirect);
+            }
+        }
+
+        let mut routines = BTreeMap::new();
+        for (&entry, &reach_by) in &reach {
+            let mut found = BTreeSet::new();
+            let mut calls = BTreeSet::new();
+            let mut pending = vec![entry];
+            while let Some(address) = pending.pop() {
+                let block = match blocks.get(&address) {
+                    Some(block) if found.insert(address) => block,
+                    _ => continue,
+                };
+                calls.extend(
+                    block
+                        .call
+                        .filter(|target| reach.contains_key(target))
+                        .map(|target| (target, CallKind::Call)),
+                );
+                let kind = if block.last().flow() == Flow::Indirect {
+                    CallKind::Indirect
+                } else {
+                    CallKind::Tail
+                };
+                for &(to, _) in &block.successors {
+                    if to != entry && reach.contains_key(&to) {
+                        calls.insert((to, kind));
+                    } else {
+                        pending.push(to);
+                    }
+                }
+            }
+            routines.insert(
+                entry,
+                Routine {
+                    entry,
+                    reach: reach_by,
+                    blocks: found.into_iter().collect(),
+                    calls: calls.into_iter().collect(),
+                },
+            );
+        }
+        CallGraph { routines, roots }
+    }
+
+    // Everything a routine goes to, whichever way.
+    fn callees(&self, entry: u16) -> impl Iterator<Item = u16> + '_ {
+        self.routines[&entry].calls.iter().map(|&(to, _)| to)
+    }
+
+    // Each loop of routines calling each other, once, starting from the
+    // lowest address in it. A routine that calls itself is a loop of one.
+    pub fn recursion(&self) -> Vec<Vec<u16>> {
+        let mut loops = Vec::new();
+        for &entry in self.routines.keys() {
+            // Shortest way back round, only through routines after this one
+            // so each loop is found from its lowest address.
+            let mut previous: BTreeMap<u16, u16> = BTreeMap::new();
+            let mut pending: VecDeque<u16> = VecDeque::new();
+            pending.push_back(entry);
+            let mut closed = None;
+            while let Some(address) = pending.pop_front() {
+                for to in self.callees(address) {
+                    if to == entry {
+                        closed = Some(address);
+                        break;
+                    }
+                    if to > entry && !previous.contains_key(&to) {
+                        previous.insert(to, address);
+                        pending.push_back(to);
+                    }
+                }
+                if closed.is_some() {
+                    break;
+                }
+            }
+            if let Some(mut address) = closed {
+                let mut path = vec![address];
+                while address != entry {
+                    address = previous[&address];
+                    path.push(address);
+                }
+                path.reverse();
+                loops.push(path);
+            }
+        }
+        loops
+    }
+
+    // Routines that nothing from the roots ever gets to.
+    pub fn unreachable(&self) -> Vec<u16> {
+        let mut seen: HashSet<u16> = self.roots.iter().cloned().collect();
+        let mut pending = self.roots.clone();
+        while let Some(address) = pending.pop() {
+            for to in self.callees(address) {
+                if seen.insert(to) {
+                    pending.push(to);
+                }
+            }
+        }
+        self.routines
+            .keys()
+            .cloned()
+            .filter(|entry| !seen.contains(entry))
+            .collect()
+    }
+
+    // Routines only ever reached through PCHL.
+    pub fn indirect(&self) -> Vec<u16> {
+        self.routines
+            .values()
+            .filter(|routine| routine.reach == Reach::Indirect)
+            .map(|routine| routine.entry)
+            .collect()
+    }
+
+    // Graphviz, one box a routine. Tail calls are dashed and PCHL dotted.
+    pub fn write_dot<W: Write>(&self, out: &mut W, labels: &Labels) -> io::Result<()> {
+        writeln!(out, "digraph calls {{")?;
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+        for routine in self.routines.values() {
+            let style = match routine.reach {
+                Reach::Entry => " [style=bold]",
+                Reach::Call => "",
+                Reach::Indirect => " [style=dashed]",
+            };
+            writeln!(out, "    \"{}\"{};", name(routine.entry, labels), style)?;
+        }
+        for routine in self.routines.values() {
+            for &(to, kind) in &routine.calls {
+                let style = match kind {
+                    CallKind::Call => "",
+                    CallKind::Tail => " [style=dashed]",
+                    CallKind::Indirect => " [style=dotted]",
+                };
+                writeln!(
+                    out,
+                    "    \"{}\" -> \"{}\"{};",
+                    name(routine.entry, labels),
+                    name(to, labels),
+                    style
+                )?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    // What calls what as an indented tree from each root, then whatever
+    // the roots never get to, then what's odd about it all.
+    pub fn write_tree<W: Write>(&self, out: &mut W, labels: &Labels) -> io::Result<()> {
+        let unreachable = self.unreachable();
+        let mut shown = HashSet::new();
+        for &root in self.roots.iter().chain(&unreachable) {
+            if !shown.contains(&root) {
+                self.write_branch(out, labels, root, &mut Vec::new(), &mut shown)?;
+            }
+        }
+        let names = |entries: &[u16]| {
+            entries
+                .iter()
+                .map(|&entry| name(entry, labels))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        writeln!(out)?;
+        for mut path in self.recursion() {
+            path.push(path[0]);
+            writeln!(out, "Recursive: {}", names(&path).replace(", ", " -> "))?;
+        }
+        if !unreachable.is_empty() {
+            writeln!(out, "Unreachable: {}", names(&unreachable))?;
+        }
+        let indirect = self.indirect();
+        if !indirect.is_empty() {
+            writeln!(out, "Only reached through PCHL: {}", names(&indirect))?;
+        }
+        Ok(())
+    }
+
+    // A routine and, indented under it, everything it calls. Anything that's
+    // already been shown once just gets its name.
+    fn write_branch<W: Write>(
+        &self,
+        out: &mut W,
+        labels: &Labels,
+        entry: u16,
+        path: &mut Vec<u16>,
+        shown: &mut HashSet<u16>,
+    ) -> io::Result<()> {
+        let indent = "  ".repeat(path.len());
+        let routine = &self.routines[&entry];
+        let how = match path.last() {
+            Some(&caller) => match self.routines[&caller]
+                .calls
+                .iter()
+                .find(|&&(to, _)| to == entry)
+            {
+                Some(&(_, CallKind::Tail)) => " (tail)",
+                Some(&(_, CallKind::Indirect)) => " (PCHL)",
+                _ => "",
+            },
+            None => "",
+        };
+        if path.contains(&entry) {
+            return writeln!(out, "{}{}{} (recursive)", indent, name(entry, labels), how);
+        }
+        if !shown.insert(entry) {
+            return writeln!(out, "{}{}{} ...", indent, name(entry, labels), how);
+        }
+        writeln!(out, "{}{}{}", indent, name(entry, labels), how)?;
+        path.push(entry);
+        let mut callees: Vec<u16> = routine.calls.iter().map(|&(to, _)| to).collect();
+        callees.dedup();
+        for to in callees {
+            self.write_branch(out, labels, to, path, shown)?;
+        }
+        path.pop();
+        Ok(())
+    }
+}
+
+// The routines in the listing, with the program starting at the roots.
+pub fn call_graph(lines: &[Line], roots: &[u16]) -> CallGraph {
+    CallGraph::new(&blocks::blocks(lines), roots)
+}
+
+fn name(address: u16, labels: &Labels) -> String {
+    match labels.get(address) {
+        Some(name) => name.to_string(),
+        None => format!("{:04x}", address),
+    }
+}
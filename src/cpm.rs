@@ -0,0 +1,781 @@
+// Enough of CP/M 2.2 to run .COM programs on the emulator. Programs load at
+// 0x0100, calls to the BDOS at 0x0005 are caught and done on the host (the
+// console is stdin and stdout, and files live in a host directory), and
+// jumping to 0x0000 for a warm boot ends the run.
+
+use cpu::{Bus, Cpu, Ram};
+use instruction::{Flow, Mnemonic, Operand, Register, RegisterPair};
+use listing::Line;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+// Where the program goes and where it starts.
+pub const TPA: u16 = 0x0100;
+pub const BDOS: u16 = 0x0005;
+// The BDOS jump at 0x0005 points here, and programs use it as the top of
+// their memory. Nothing is there since the call gets caught before the jump.
+const BDOS_ENTRY: u16 = 0xfe06;
+// The BIOS jump table, with the warm boot entry that 0x0000 jumps to.
+const BIOS: u16 = 0xff00;
+const BIOS_ENTRIES: u16 = 17;
+// The default FCB, second FCB and DMA buffer the CCP sets up.
+const FCB: u16 = 0x005c;
+const FCB2: u16 = 0x006c;
+const DEFAULT_DMA: u16 = 0x0080;
+const RECORD: usize = 128;
+// Records in an extent, EX counts these.
+const EXTENT: usize = 128;
+// Console status, from both BDOS 11 and the BIOS, when a key is waiting.
+const CONSOLE_READY: u8 = 0xff;
+// What CP/M uses to pad out the end of a text file.
+const END_OF_FILE: u8 = 0x1a;
+
+// The BDOS function that goes in register C.
+pub fn bdos_function_name(function: u8) -> Option<&'static str> {
+    let name = match function {
+        0 => "system reset",
+        1 => "console input",
+        2 => "console output",
+        3 => "reader input",
+        4 => "punch output",
+        5 => "list output",
+        6 => "direct console I/O",
+        7 => "get I/O byte",
+        8 => "set I/O byte",
+        9 => "print string",
+        10 => "read console buffer",
+        11 => "get console status",
+        12 => "return version number",
+        13 => "reset disk system",
+        14 => "select disk",
+        15 => "open file",
+        16 => "close file",
+        17 => "search for first",
+        18 => "search for next",
+        19 => "delete file",
+        20 => "read sequential",
+        21 => "write sequential",
+        22 => "make file",
+        23 => "rename file",
+        24 => "return login vector",
+        25 => "return current disk",
+        26 => "set DMA address",
+        27 => "get allocation vector",
+        28 => "write protect disk",
+        29 => "get read only vector",
+        30 => "set file attributes",
+        31 => "get disk parameters",
+        32 => "get/set user code",
+        33 => "read random",
+        34 => "write random",
+        35 => "compute file size",
+        36 => "set random record",
+        37 => "reset drive",
+        40 => "write random with zero fill",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Finds `MVI C,n` followed by `CALL 0005` (with nothing jumping in between)
+// and names the BDOS function being called, keyed by the CALL's address.
+pub fn bdos_calls(lines: &[Line]) -> BTreeMap<u16, String> {
+    let mut calls = BTreeMap::new();
+    let mut function = None;
+    for line in lines {
+        let instruction = match *line {
+            Line::Instruction(ref instruction) => instruction,
+            Line::Data { .. } => {
+                function = None;
+                continue;
+            }
+        };
+        match (instruction.mnemonic, &instruction.operands[..]) {
+            (Mnemonic::Mvi, &[Operand::Reg(Register::C), Operand::Byte(value)]) => {
+                function = Some(value);
+                continue;
+            }
+            (_, &[Operand::Address(BDOS)]) if instruction.target().is_some() => {
+                let comment = match function.map(|value| (value, bdos_function_name(value))) {
+                    Some((value, Some(name))) => format!("BDOS {}: {}", value, name),
+                    Some((value, None)) => format!("BDOS {}", value),
+                    None => "BDOS".to_string(),
+                };
+                calls.insert(instruction.address, comment);
+            }
+            _ => {}
+        }
+        // Anything that touches C or leaves the straight line loses track of it.
+        let changes_c = instruction.operands.first() == Some(&Operand::Reg(Register::C))
+            || instruction.operands.first() == Some(&Operand::Pair(RegisterPair::B));
+        if changes_c || instruction.flow() != Flow::Next {
+            function = None;
+        }
+    }
+    calls
+}
+
+pub struct Cpm {
+    pub cpu: Cpu,
+    pub ram: Ram,
+    // Host directory standing in for drive A:.
+    directory: PathBuf,
+    dma: u16,
+    // Open files by their CP/M name, e.g. "FOO.TXT".
+    files: HashMap<String, File>,
+    // What's left to hand out for search for next.
+    search: Vec<String>,
+    input: Input,
+    output: Box<dyn Write>,
+    finished: bool,
+}
+
+// The console's input, read on a thread of its own so console status can
+// tell whether a key is waiting without stopping to wait for one. The thread
+// starts on the first look, so input that's swapped out is never touched.
+struct Input {
+    reader: Option<Box<dyn Read + Send>>,
+    bytes: Option<Receiver<u8>>,
+    // Taken off the thread by a status check but not read yet.
+    waiting: Option<u8>,
+}
+
+impl Input {
+    fn new(reader: Box<dyn Read + Send>) -> Input {
+        Input {
+            reader: Some(reader),
+            bytes: None,
+            waiting: None,
+        }
+    }
+
+    fn bytes(&mut self) -> &Receiver<u8> {
+        if let Some(mut reader) = self.reader.take() {
+            let (sender, bytes) = mpsc::channel();
+            thread::spawn(move || {
+                let mut byte = [0];
+                while let Ok(1) = reader.read(&mut byte) {
+                    if sender.send(byte[0]).is_err() {
+                        break;
+                    }
+                }
+            });
+            self.bytes = Some(bytes);
+        }
+        self.bytes.as_ref().unwrap()
+    }
+
+    // Whether reading would get something straight away. At the end of the
+    // input it would, an END_OF_FILE.
+    fn ready(&mut self) -> bool {
+        if self.waiting.is_some() {
+            return true;
+        }
+        match self.bytes().try_recv() {
+            Ok(byte) => {
+                self.waiting = Some(byte);
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => true,
+        }
+    }
+
+    // The next byte, waiting for it if need be. None at the end.
+    fn read(&mut self) -> Option<u8> {
+        match self.waiting.take() {
+            Some(byte) => Some(byte),
+            None => self.bytes().recv().ok(),
+        }
+    }
+}
+
+impl Cpm {
+    // Loads a .COM program with the given command line tail (what came
+    // after the program name, like `B:FOO.TXT /X`).
+    pub fn new(program: &[u8], arguments: &str, directory: PathBuf) -> Cpm {
+        let mut ram = Ram::new();
+        let end = (TPA as usize + program.len()).min(BDOS_ENTRY as usize);
+        ram.bytes[TPA as usize..end].copy_from_slice(&program[..end - TPA as usize]);
+        // JMP to the warm boot and JMP to the BDOS in the zero page.
+        write_jump(&mut ram, 0x0000, BIOS + 3);
+        write_jump(&mut ram, BDOS, BDOS_ENTRY);
+        for entry in 0..BIOS_ENTRIES {
+            // RET, although the calls get caught before they get here.
+            ram.bytes[(BIOS + entry * 3) as usize] = 0xc9;
+        }
+        let arguments = arguments.trim().to_uppercase();
+        let mut names = arguments.split_whitespace();
+        set_fcb_name(&mut ram, FCB, names.next().unwrap_or(""));
+        set_fcb_name(&mut ram, FCB2, names.next().unwrap_or(""));
+        // The command tail is a length then the text, with a leading space.
+        let tail = if arguments.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", arguments)
+        };
+        let tail = &tail.as_bytes()[..tail.len().min(RECORD - 1)];
+        ram.bytes[DEFAULT_DMA as usize] = tail.len() as u8;
+        ram.bytes[DEFAULT_DMA as usize + 1..DEFAULT_DMA as usize + 1 + tail.len()]
+            .copy_from_slice(tail);
+
+        let mut cpu = Cpu::new();
+        cpu.pc = TPA;
+        // The CCP leaves a return address to 0x0000 on the stack, so a plain
+        // RET ends the program too.
+        cpu.sp = BDOS_ENTRY;
+        cpu.sp -= 2;
+        ram.bytes[cpu.sp as usize] = 0;
+        ram.bytes[cpu.sp as usize + 1] = 0;
+        Cpm {
+            cpu,
+            ram,
+            directory,
+            dma: DEFAULT_DMA,
+            files: HashMap::new(),
+            search: Vec::new(),
+            input: Input::new(Box::new(io::stdin())),
+            output: Box::new(io::stdout()),
+            finished: false,
+        }
+    }
+
+    // Swaps the console over from stdin and stdout.
+    pub fn set_console(&mut self, input: Box<dyn Read + Send>, output: Box<dyn Write>) {
+        self.input = Input::new(input);
+        self.output = output;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    // Runs until the program warm boots.
+    pub fn run(&mut self) {
+        while !self.finished {
+            self.step();
+        }
+    }
+
+    // Runs one instruction, or one whole BDOS or BIOS call if that's where PC is.
+    pub fn step(&mut self) {
+        if self.finished {
+            return;
+        }
+        let pc = self.cpu.pc;
+        if pc == 0x0000 {
+            self.finished = true;
+        } else if pc == BDOS {
+            self.bdos();
+            self.return_from_call();
+        } else if (BIOS..BIOS + BIOS_ENTRIES * 3).contains(&pc) && (pc - BIOS).is_multiple_of(3) {
+            self.bios((pc - BIOS) / 3);
+            if !self.finished {
+                self.return_from_call();
+            }
+        } else {
+            self.cpu.step(&mut self.ram);
+        }
+        if self.finished {
+            self.output.flush().ok();
+        }
+    }
+
+    fn return_from_call(&mut self) {
+        let sp = self.cpu.sp;
+        self.cpu.pc =
+            u16::from(self.ram.read(sp)) | u16::from(self.ram.read(sp.wrapping_add(1))) << 8;
+        self.cpu.sp = sp.wrapping_add(2);
+    }
+
+    fn console_in(&mut self) -> u8 {
+        self.output.flush().ok();
+        match self.input.read() {
+            // CP/M wants carriage returns where the host has line feeds.
+            Some(b'\n') => b'\r',
+            Some(byte) => byte,
+            None => END_OF_FILE,
+        }
+    }
+
+    // 0 unless there's a key waiting, so programs can check for one (like a
+    // ^C) between other things without stopping.
+    fn console_status(&mut self) -> u8 {
+        if self.input.ready() {
+            CONSOLE_READY
+        } else {
+            0
+        }
+    }
+
+    fn console_out(&mut self, byte: u8) {
+        // Programs end lines with CR LF, the host only wants the LF.
+        if byte != b'\r' {
+            self.output.write_all(&[byte & 0x7f]).ok();
+        }
+    }
+
+    fn bios(&mut self, entry: u16) {
+        match entry {
+            // Cold and warm boot.
+            0 | 1 => self.finished = true,
+            2 => self.cpu.a = self.console_status(),
+            3 => self.cpu.a = self.console_in(),
+            4 => {
+                let c = self.cpu.c;
+                self.console_out(c)
+            }
+            _ => {}
+        }
+    }
+
+    fn bdos(&mut self) {
+        let de = self.cpu.de();
+        let e = self.cpu.e;
+        let result: u16 = match self.cpu.c {
+            0 => {
+                self.finished = true;
+                0
+            }
+            1 => {
+                let byte = self.console_in();
+                self.console_out(byte);
+                u16::from(byte)
+            }
+            2 => {
+                self.console_out(e);
+                0
+            }
+            6 => match e {
+                // A key if there's one waiting, otherwise 0.
+                0xff if self.console_status() == 0 => 0,
+                0xff => u16::from(self.console_in()),
+                0xfe => 0,
+                _ => {
+                    self.console_out(e);
+                    0
+                }
+            },
+            9 => {
+                // A string with no `$` stops where the address wraps round,
+                // rather than going round memory for ever.
+                let mut address = de;
+                loop {
+                    let byte = self.ram.read(address);
+                    if byte == b'$' {
+                        break;
+                    }
+                    self.console_out(byte);
+                    address = address.wrapping_add(1);
+                    if address == 0 {
+                        break;
+                    }
+                }
+                0
+            }
+            10 => {
+                self.read_console_buffer(de);
+                0
+            }
+            11 => u16::from(self.console_status()),
+            // CP/M 2.2
+            12 => 0x0022,
+            13 => {
+                self.dma = DEFAULT_DMA;
+                0
+            }
+            14 | 25 | 32 => 0,
+            15 => self.open_file(de),
+            16 => self.close_file(de),
+            17 => self.search_first(de),
+            18 => self.search_next(),
+            19 => self.delete_file(de),
+            20 => {
+                let record = sequential_record(&self.ram, de);
+                let result = self.read_record(de, record);
+                if result == 0 {
+                    set_sequential_record(&mut self.ram, de, record + 1);
+                }
+                result
+            }
+            21 => {
+                let record = sequential_record(&self.ram, de);
+                let result = self.write_record(de, record);
+                if result == 0 {
+                    set_sequential_record(&mut self.ram, de, record + 1);
+                }
+                result
+            }
+            22 => self.make_file(de),
+            23 => self.rename_file(de),
+            // Only drive A: is logged in.
+            24 => 0x0001,
+            26 => {
+                self.dma = de;
+                0
+            }
+            33 => {
+                let record = random_record(&self.ram, de);
+                set_sequential_record(&mut self.ram, de, record);
+                self.read_record(de, record)
+            }
+            34 | 40 => {
+                let record = random_record(&self.ram, de);
+                set_sequential_record(&mut self.ram, de, record);
+                self.write_record(de, record)
+            }
+            35 => {
+                let size = self
+                    .host_path(de)
+                    .and_then(|path| fs::metadata(path).ok())
+                    .map_or(0, |metadata| metadata.len() as usize);
+                set_random_record(&mut self.ram, de, size.div_ceil(RECORD));
+                0
+            }
+            36 => {
+                let record = sequential_record(&self.ram, de);
+                set_random_record(&mut self.ram, de, record);
+                0
+            }
+            _ => 0,
+        };
+        // Results come back in HL, with A = L and B = H for older programs.
+        self.cpu.set_pair(RegisterPair::H, result);
+        self.cpu.a = result as u8;
+        self.cpu.b = (result >> 8) as u8;
+    }
+
+    fn read_console_buffer(&mut self, buffer: u16) {
+        let size = self.ram.read(buffer) as usize;
+        let mut count = 0;
+        while count < size {
+            match self.console_in() {
+                b'\r' | END_OF_FILE => break,
+                byte => {
+                    self.ram.write(buffer.wrapping_add(2 + count as u16), byte);
+                    count += 1;
+                }
+            }
+        }
+        self.ram.write(buffer.wrapping_add(1), count as u8);
+        self.console_out(b'\n');
+    }
+
+    // The host file for an FCB, if there's one with that name in the directory.
+    fn host_path(&self, fcb: u16) -> Option<PathBuf> {
+        let name = fcb_name(&self.ram, fcb);
+        self.host_names()
+            .into_iter()
+            .find(|host| host.to_uppercase() == name)
+            .map(|host| self.directory.join(host))
+    }
+
+    fn host_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(&self.directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_file())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    fn open_file(&mut self, fcb: u16) -> u16 {
+        let path = match self.host_path(fcb) {
+            Some(path) => path,
+            None => return 0xff,
+        };
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .or_else(|_| File::open(&path))
+        {
+            Ok(file) => {
+                self.files.insert(fcb_name(&self.ram, fcb), file);
+                set_sequential_record(&mut self.ram, fcb, 0);
+                0
+            }
+            Err(_) => 0xff,
+        }
+    }
+
+    fn close_file(&mut self, fcb: u16) -> u16 {
+        match self.files.remove(&fcb_name(&self.ram, fcb)) {
+            Some(mut file) => {
+                file.flush().ok();
+                0
+            }
+            None => 0xff,
+        }
+    }
+
+    fn make_file(&mut self, fcb: u16) -> u16 {
+        let name = fcb_name(&self.ram, fcb);
+        if !is_file_name(&name) {
+            return 0xff;
+        }
+        let path = self
+            .host_path(fcb)
+            .unwrap_or_else(|| self.directory.join(&name));
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+        {
+            Ok(file) => {
+                self.files.insert(name, file);
+                set_sequential_record(&mut self.ram, fcb, 0);
+                0
+            }
+            Err(_) => 0xff,
+        }
+    }
+
+    fn delete_file(&mut self, fcb: u16) -> u16 {
+        let pattern = fcb_pattern(&self.ram, fcb);
+        let mut result = 0xff;
+        for name in self.host_names() {
+            if matches_pattern(&name, &pattern) {
+                self.files.remove(&name.to_uppercase());
+                if fs::remove_file(self.directory.join(&name)).is_ok() {
+                    result = 0;
+                }
+            }
+        }
+        result
+    }
+
+    fn rename_file(&mut self, fcb: u16) -> u16 {
+        let path = match self.host_path(fcb) {
+            Some(path) => path,
+            None => return 0xff,
+        };
+        // The new name sits in the second half of the FCB.
+        let new_name = fcb_name(&self.ram, fcb.wrapping_add(16));
+        if !is_file_name(&new_name) {
+            return 0xff;
+        }
+        match fs::rename(path, self.directory.join(new_name)) {
+            Ok(()) => 0,
+            Err(_) => 0xff,
+        }
+    }
+
+    fn search_first(&mut self, fcb: u16) -> u16 {
+        let pattern = fcb_pattern(&self.ram, fcb);
+        self.search = self
+            .host_names()
+            .into_iter()
+            .filter(|name| matches_pattern(name, &pattern))
+            .rev()
+            .collect();
+        self.search_next()
+    }
+
+    // Writes the next match into the DMA buffer as a directory entry.
+    fn search_next(&mut self) -> u16 {
+        let name = match self.search.pop() {
+            Some(name) => name,
+            None => return 0xff,
+        };
+        let entry = padded_name(&name).unwrap_or_default();
+        let size =
+            fs::metadata(self.directory.join(&name)).map_or(0, |metadata| metadata.len() as usize);
+        let records = size.div_ceil(RECORD).min(EXTENT);
+        let dma = self.dma;
+        for offset in 0..32 {
+            self.ram.write(dma.wrapping_add(offset), 0);
+        }
+        for (offset, &byte) in entry.as_bytes().iter().enumerate() {
+            self.ram.write(dma.wrapping_add(1 + offset as u16), byte);
+        }
+        self.ram.write(dma.wrapping_add(15), records as u8);
+        0
+    }
+
+    fn read_record(&mut self, fcb: u16, record: usize) -> u16 {
+        let name = fcb_name(&self.ram, fcb);
+        let mut buffer = [END_OF_FILE; RECORD];
+        let read = match self.files.get_mut(&name) {
+            Some(file) => file
+                .seek(SeekFrom::Start((record * RECORD) as u64))
+                .and_then(|_| read_up_to(file, &mut buffer)),
+            None => return 0xff,
+        };
+        match read {
+            Ok(0) | Err(_) => 1,
+            Ok(_) => {
+                let dma = self.dma;
+                for (offset, &byte) in buffer.iter().enumerate() {
+                    self.ram.write(dma.wrapping_add(offset as u16), byte);
+                }
+                0
+            }
+        }
+    }
+
+    fn write_record(&mut self, fcb: u16, record: usize) -> u16 {
+        let name = fcb_name(&self.ram, fcb);
+        let dma = self.dma;
+        let buffer: Vec<u8> = (0..RECORD as u16)
+            .map(|offset| self.ram.read(dma.wrapping_add(offset)))
+            .collect();
+        match self.files.get_mut(&name) {
+            Some(file) => match file
+                .seek(SeekFrom::Start((record * RECORD) as u64))
+                .and_then(|_| file.write_all(&buffer))
+            {
+                Ok(()) => 0,
+                // Disk full is the closest thing CP/M has.
+                Err(_) => 2,
+            },
+            None => 0xff,
+        }
+    }
+}
+
+fn read_up_to(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match file.read(&mut buffer[total..])? {
+            0 => break,
+            read => total += read,
+        }
+    }
+    Ok(total)
+}
+
+fn write_jump(ram: &mut Ram, address: u16, target: u16) {
+    let address = address as usize;
+    ram.bytes[address] = 0xc3;
+    ram.bytes[address + 1] = target as u8;
+    ram.bytes[address + 2] = (target >> 8) as u8;
+}
+
+// Fills in an FCB's drive and name from something like `B:FOO.TXT`.
+fn set_fcb_name(ram: &mut Ram, fcb: u16, name: &str) {
+    let fcb = fcb as usize;
+    for byte in &mut ram.bytes[fcb..fcb + 16] {
+        *byte = 0;
+    }
+    let (drive, name) = match name.find(':') {
+        Some(1) => (name.as_bytes()[0].wrapping_sub(b'A' - 1), &name[2..]),
+        _ => (0, name),
+    };
+    ram.bytes[fcb] = drive;
+    let padded = padded_name(name).unwrap_or_else(|| " ".repeat(11));
+    ram.bytes[fcb + 1..fcb + 12].copy_from_slice(padded.as_bytes());
+}
+
+// `FOO.TXT` as the 11 characters CP/M keeps it as, `FOO     TXT`. `*` fills
+// the rest of the name or type with `?`. None if it won't fit.
+fn padded_name(name: &str) -> Option<String> {
+    let name = name.to_uppercase();
+    let (base, extension) = match name.rfind('.') {
+        Some(dot) => (&name[..dot], &name[dot + 1..]),
+        None => (&name[..], ""),
+    };
+    let pad = |part: &str, width: usize| -> Option<String> {
+        let (mut part, fill) = match part.find('*') {
+            Some(star) => (part[..star].to_string(), '?'),
+            None => (part.to_string(), ' '),
+        };
+        if part.len() > width || !part.is_ascii() {
+            return None;
+        }
+        while part.len() < width {
+            part.push(fill);
+        }
+        Some(part)
+    };
+    Some(format!("{}{}", pad(base, 8)?, pad(extension, 3)?))
+}
+
+// The 11 name characters from an FCB with the attribute bits taken off.
+fn fcb_pattern(ram: &Ram, fcb: u16) -> String {
+    (1..12)
+        .map(|offset| (peek(ram, fcb.wrapping_add(offset)) & 0x7f) as char)
+        .collect()
+}
+
+// The FCB's file name the way the host sees it, e.g. `FOO.TXT`.
+fn fcb_name(ram: &Ram, fcb: u16) -> String {
+    let pattern = fcb_pattern(ram, fcb);
+    let (base, extension) = pattern.split_at(8);
+    let (base, extension) = (base.trim_end(), extension.trim_end());
+    if extension.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}.{}", base, extension)
+    }
+}
+
+// Whether a name from an FCB is a plain 8.3 name, so it can't be used to
+// get out of the directory (`../FOO`, `/ETC/X`) or be a wildcard.
+fn is_file_name(name: &str) -> bool {
+    let (base, extension) = match name.find('.') {
+        Some(dot) => (&name[..dot], &name[dot + 1..]),
+        None => (name, ""),
+    };
+    let allowed = |part: &str| {
+        part.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$&'+-@^`{}~".contains(c))
+    };
+    !base.is_empty()
+        && base.len() <= 8
+        && extension.len() <= 3
+        && allowed(base)
+        && allowed(extension)
+}
+
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    match padded_name(name) {
+        Some(padded) => padded
+            .chars()
+            .zip(pattern.chars())
+            .all(|(c, p)| p == '?' || c == p),
+        None => false,
+    }
+}
+
+// A byte of memory for code that can't borrow the RAM mutably to `read` it.
+// Addresses wrap like the CPU's do, so an FCB at the top of memory doesn't
+// run off the end.
+fn peek(ram: &Ram, address: u16) -> u8 {
+    ram.bytes[address as usize]
+}
+
+// Sequential reads and writes work through the file by EX (which extent)
+// and CR (which record in the extent).
+fn sequential_record(ram: &Ram, fcb: u16) -> usize {
+    let extent = peek(ram, fcb.wrapping_add(12)) as usize;
+    let record = peek(ram, fcb.wrapping_add(32)) as usize;
+    extent * EXTENT + record
+}
+
+fn set_sequential_record(ram: &mut Ram, fcb: u16, record: usize) {
+    ram.write(fcb.wrapping_add(12), (record / EXTENT) as u8);
+    ram.write(fcb.wrapping_add(32), (record % EXTENT) as u8);
+}
+
+// Random access uses R0 and R1 as a 16 bit record number.
+fn random_record(ram: &Ram, fcb: u16) -> usize {
+    peek(ram, fcb.wrapping_add(33)) as usize | (peek(ram, fcb.wrapping_add(34)) as usize) << 8
+}
+
+fn set_random_record(ram: &mut Ram, fcb: u16, record: usize) {
+    ram.write(fcb.wrapping_add(33), record as u8);
+    ram.write(fcb.wrapping_add(34), (record >> 8) as u8);
+    ram.write(fcb.wrapping_add(35), (record >> 16) as u8);
+}
@@ -0,0 +1,534 @@
+// Runs 8080 code. Each step decodes the next instruction with the same
+// decoder the disassembler uses and then carries it out, so anything the
+// listing says an instruction does is what happens here.
+
+use decoder::decode;
+use image::Image;
+use instruction::{Flow, Instruction, Mnemonic, Operand, Register, RegisterPair};
+use timing;
+
+// Everything outside the CPU: memory and the I/O ports.
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+    // IN port
+    fn input(&mut self, port: u8) -> u8;
+    // OUT port
+    fn output(&mut self, port: u8, value: u8);
+}
+
+// 64K of RAM and no I/O, enough to run plain code.
+#[derive(Clone)]
+pub struct Ram {
+    pub bytes: Vec<u8>,
+}
+
+impl Ram {
+    pub fn new() -> Ram {
+        Ram {
+            bytes: vec![0; 0x10000],
+        }
+    }
+
+    pub fn from_image(image: &Image) -> Ram {
+        let mut ram = Ram::new();
+        for region in image.regions() {
+            let start = region.start as usize;
+            ram.bytes[start..region.end()].copy_from_slice(&region.bytes);
+        }
+        ram
+    }
+}
+
+impl Default for Ram {
+    fn default() -> Ram {
+        Ram::new()
+    }
+}
+
+impl Bus for Ram {
+    fn read(&mut self, address: u16) -> u8 {
+        self.bytes[address as usize]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.bytes[address as usize] = value;
+    }
+
+    // Nothing's connected, so the data bus floats high.
+    fn input(&mut self, _port: u8) -> u8 {
+        0xff
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Flags {
+    pub sign: bool,
+    pub zero: bool,
+    // AC, the carry out of bit 3. Only DAA really uses it.
+    pub aux_carry: bool,
+    // Set when the result has an even number of 1 bits.
+    pub parity: bool,
+    pub carry: bool,
+}
+
+impl Flags {
+    // The flags as they sit in the low byte of PSW: S Z 0 AC 0 P 1 CY.
+    pub fn to_byte(self) -> u8 {
+        (self.sign as u8) << 7
+            | (self.zero as u8) << 6
+            | (self.aux_carry as u8) << 4
+            | (self.parity as u8) << 2
+            | 0x02
+            | self.carry as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Flags {
+        Flags {
+            sign: byte & 0x80 != 0,
+            zero: byte & 0x40 != 0,
+            aux_carry: byte & 0x10 != 0,
+            parity: byte & 0x04 != 0,
+            carry: byte & 0x01 != 0,
+        }
+    }
+
+    // S, Z and P all just depend on the result.
+    fn set_result(&mut self, value: u8) {
+        self.sign = value & 0x80 != 0;
+        self.zero = value == 0;
+        self.parity = value.count_ones().is_multiple_of(2);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cpu {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub flags: Flags,
+    pub interrupts_enabled: bool,
+    // Stopped by HLT until an interrupt comes along.
+    pub halted: bool,
+    // EI doesn't let interrupts in until after the instruction following it.
+    interrupt_delay: bool,
+    // Total T-states run so far.
+    pub cycles: u64,
+}
+
+impl Cpu {
+    pub fn new() -> Cpu {
+        Cpu::default()
+    }
+
+    // Decodes the instruction at PC without running it.
+    pub fn fetch<B: Bus>(&self, bus: &mut B) -> Instruction {
+        let bytes = [
+            bus.read(self.pc),
+            bus.read(self.pc.wrapping_add(1)),
+            bus.read(self.pc.wrapping_add(2)),
+        ];
+        // Three bytes is always enough for one instruction.
+        decode(&bytes, self.pc).unwrap()
+    }
+
+    // Runs one instruction and returns how many T-states it took.
+    pub fn step<B: Bus>(&mut self, bus: &mut B) -> u32 {
+        self.interrupt_delay = false;
+        if self.halted {
+            // HLT just idles until an interrupt.
+            self.cycles += 4;
+            return 4;
+        }
+        let instruction = self.fetch(bus);
+        self.pc = instruction.next_address();
+        let timing = timing::timing(instruction.opcode);
+        let cycles = if self.execute(&instruction, bus) {
+            timing.taken.unwrap_or(timing.cycles)
+        } else {
+            timing.cycles
+        };
+        let cycles = u32::from(cycles);
+        self.cycles += u64::from(cycles);
+        cycles
+    }
+
+    // Interrupts the CPU with an RST, which is how 8080 interrupts work.
+    // Returns false if interrupts are disabled so it didn't happen.
+    pub fn interrupt<B: Bus>(&mut self, bus: &mut B, number: u8) -> bool {
+        if !self.interrupts_enabled || self.interrupt_delay {
+            return false;
+        }
+        self.interrupts_enabled = false;
+        self.halted = false;
+        let pc = self.pc;
+        self.push(bus, pc);
+        self.pc = u16::from(number & 7) * 8;
+        self.cycles += 11;
+        true
+    }
+
+    pub fn bc(&self) -> u16 {
+        u16::from(self.b) << 8 | u16::from(self.c)
+    }
+
+    pub fn de(&self) -> u16 {
+        u16::from(self.d) << 8 | u16::from(self.e)
+    }
+
+    pub fn hl(&self) -> u16 {
+        u16::from(self.h) << 8 | u16::from(self.l)
+    }
+
+    pub fn register<B: Bus>(&self, bus: &mut B, register: Register) -> u8 {
+        match register {
+            Register::B => self.b,
+            Register::C => self.c,
+            Register::D => self.d,
+            Register::E => self.e,
+            Register::H => self.h,
+            Register::L => self.l,
+            Register::M => bus.read(self.hl()),
+            Register::A => self.a,
+        }
+    }
+
+    pub fn set_register<B: Bus>(&mut self, bus: &mut B, register: Register, value: u8) {
+        match register {
+            Register::B => self.b = value,
+            Register::C => self.c = value,
+            Register::D => self.d = value,
+            Register::E => self.e = value,
+            Register::H => self.h = value,
+            Register::L => self.l = value,
+            Register::M => bus.write(self.hl(), value),
+            Register::A => self.a = value,
+        }
+    }
+
+    pub fn pair(&self, pair: RegisterPair) -> u16 {
+        match pair {
+            RegisterPair::B => self.bc(),
+            RegisterPair::D => self.de(),
+            RegisterPair::H => self.hl(),
+            RegisterPair::SP => self.sp,
+            RegisterPair::PSW => u16::from(self.a) << 8 | u16::from(self.flags.to_byte()),
+        }
+    }
+
+    pub fn set_pair(&mut self, pair: RegisterPair, value: u16) {
+        let (high, low) = ((value >> 8) as u8, value as u8);
+        match pair {
+            RegisterPair::B => {
+                self.b = high;
+                self.c = low;
+            }
+            RegisterPair::D => {
+                self.d = high;
+                self.e = low;
+            }
+            RegisterPair::H => {
+                self.h = high;
+                self.l = low;
+            }
+            RegisterPair::SP => self.sp = value,
+            RegisterPair::PSW => {
+                self.a = high;
+                self.flags = Flags::from_byte(low);
+            }
+        }
+    }
+
+    fn push<B: Bus>(&mut self, bus: &mut B, value: u16) {
+        self.sp = self.sp.wrapping_sub(2);
+        write_word(bus, self.sp, value);
+    }
+
+    fn pop<B: Bus>(&mut self, bus: &mut B) -> u16 {
+        let value = read_word(bus, self.sp);
+        self.sp = self.sp.wrapping_add(2);
+        value
+    }
+
+    // Whether the condition in a Jcc, Ccc or Rcc mnemonic holds.
+    fn condition(&self, mnemonic: Mnemonic) -> bool {
+        match mnemonic {
+            Mnemonic::Jnz | Mnemonic::Cnz | Mnemonic::Rnz => !self.flags.zero,
+            Mnemonic::Jz | Mnemonic::Cz | Mnemonic::Rz => self.flags.zero,
+            Mnemonic::Jnc | Mnemonic::Cnc | Mnemonic::Rnc => !self.flags.carry,
+            Mnemonic::Jc | Mnemonic::Cc | Mnemonic::Rc => self.flags.carry,
+            Mnemonic::Jpo | Mnemonic::Cpo | Mnemonic::Rpo => !self.flags.parity,
+            Mnemonic::Jpe | Mnemonic::Cpe | Mnemonic::Rpe => self.flags.parity,
+            Mnemonic::Jp | Mnemonic::Cp | Mnemonic::Rp => !self.flags.sign,
+            Mnemonic::Jm | Mnemonic::Cm | Mnemonic::Rm => self.flags.sign,
+            _ => true,
+        }
+    }
+
+    // A + value + carry in, setting every flag. SUB and friends come through
+    // here too as A + ~value + 1, which is how the 8080 gets AC and CY for them.
+    fn add(&mut self, value: u8, carry: bool) -> u8 {
+        let result = u16::from(self.a) + u16::from(value) + carry as u16;
+        self.flags.aux_carry = (self.a & 0x0f) + (value & 0x0f) + carry as u8 > 0x0f;
+        self.flags.carry = result > 0xff;
+        self.flags.set_result(result as u8);
+        result as u8
+    }
+
+    fn subtract(&mut self, value: u8, borrow: bool) -> u8 {
+        let result = self.add(!value, !borrow);
+        // The carry out of the addition is the opposite of a borrow.
+        self.flags.carry = !self.flags.carry;
+        result
+    }
+
+    fn and(&mut self, value: u8) {
+        // The 8080 sets AC from bit 3 of either operand, unlike the 8085.
+        self.flags.aux_carry = (self.a | value) & 0x08 != 0;
+        self.a &= value;
+        self.flags.carry = false;
+        self.flags.set_result(self.a);
+    }
+
+    fn logic(&mut self, result: u8) {
+        self.a = result;
+        self.flags.carry = false;
+        self.flags.aux_carry = false;
+        self.flags.set_result(result);
+    }
+
+    // Carries out an instruction whose PC has already been moved on. Returns
+    // true if it was a conditional call or return that was taken, since those
+    // take longer.
+    fn execute<B: Bus>(&mut self, instruction: &Instruction, bus: &mut B) -> bool {
+        let mnemonic = instruction.mnemonic;
+        match (mnemonic, &instruction.operands[..]) {
+            (Mnemonic::Nop, _) => {}
+            (Mnemonic::Lxi, &[Operand::Pair(pair), Operand::Word(value)]) => {
+                self.set_pair(pair, value)
+            }
+            (Mnemonic::Stax, &[Operand::Pair(pair)]) => {
+                let address = self.pair(pair);
+                bus.write(address, self.a);
+            }
+            (Mnemonic::Ldax, &[Operand::Pair(pair)]) => self.a = bus.read(self.pair(pair)),
+            (Mnemonic::Inx, &[Operand::Pair(pair)]) => {
+                let value = self.pair(pair).wrapping_add(1);
+                self.set_pair(pair, value);
+            }
+            (Mnemonic::Dcx, &[Operand::Pair(pair)]) => {
+                let value = self.pair(pair).wrapping_sub(1);
+                self.set_pair(pair, value);
+            }
+            (Mnemonic::Inr, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register).wrapping_add(1);
+                self.flags.aux_carry = value & 0x0f == 0;
+                self.flags.set_result(value);
+                self.set_register(bus, register, value);
+            }
+            (Mnemonic::Dcr, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register).wrapping_sub(1);
+                self.flags.aux_carry = value & 0x0f != 0x0f;
+                self.flags.set_result(value);
+                self.set_register(bus, register, value);
+            }
+            (Mnemonic::Mvi, &[Operand::Reg(register), Operand::Byte(value)]) => {
+                self.set_register(bus, register, value)
+            }
+            (Mnemonic::Dad, &[Operand::Pair(pair)]) => {
+                let result = u32::from(self.hl()) + u32::from(self.pair(pair));
+                self.flags.carry = result > 0xffff;
+                self.set_pair(RegisterPair::H, result as u16);
+            }
+            (Mnemonic::Rlc, _) => {
+                self.flags.carry = self.a & 0x80 != 0;
+                self.a = self.a.rotate_left(1);
+            }
+            (Mnemonic::Rrc, _) => {
+                self.flags.carry = self.a & 0x01 != 0;
+                self.a = self.a.rotate_right(1);
+            }
+            (Mnemonic::Ral, _) => {
+                let carry = self.flags.carry;
+                self.flags.carry = self.a & 0x80 != 0;
+                self.a = self.a << 1 | carry as u8;
+            }
+            (Mnemonic::Rar, _) => {
+                let carry = self.flags.carry;
+                self.flags.carry = self.a & 0x01 != 0;
+                self.a = self.a >> 1 | (carry as u8) << 7;
+            }
+            (Mnemonic::Shld, &[Operand::Address(address)]) => {
+                let value = self.hl();
+                write_word(bus, address, value);
+            }
+            (Mnemonic::Lhld, &[Operand::Address(address)]) => {
+                let value = read_word(bus, address);
+                self.set_pair(RegisterPair::H, value);
+            }
+            (Mnemonic::Daa, _) => {
+                // Adjusts A back into two BCD digits after an addition.
+                let mut correction = 0;
+                let mut carry = self.flags.carry;
+                let (high, low) = (self.a >> 4, self.a & 0x0f);
+                if self.flags.aux_carry || low > 9 {
+                    correction |= 0x06;
+                }
+                if self.flags.carry || high > 9 || (high >= 9 && low > 9) {
+                    correction |= 0x60;
+                    carry = true;
+                }
+                self.a = self.add(correction, false);
+                self.flags.carry = carry;
+            }
+            (Mnemonic::Cma, _) => self.a = !self.a,
+            (Mnemonic::Sta, &[Operand::Address(address)]) => bus.write(address, self.a),
+            (Mnemonic::Lda, &[Operand::Address(address)]) => self.a = bus.read(address),
+            (Mnemonic::Stc, _) => self.flags.carry = true,
+            (Mnemonic::Cmc, _) => self.flags.carry = !self.flags.carry,
+            (Mnemonic::Mov, &[Operand::Reg(to), Operand::Reg(from)]) => {
+                let value = self.register(bus, from);
+                self.set_register(bus, to, value);
+            }
+            (Mnemonic::Hlt, _) => self.halted = true,
+            (Mnemonic::Add, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register);
+                self.a = self.add(value, false);
+            }
+            (Mnemonic::Adc, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register);
+                let carry = self.flags.carry;
+                self.a = self.add(value, carry);
+            }
+            (Mnemonic::Sub, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register);
+                self.a = self.subtract(value, false);
+            }
+            (Mnemonic::Sbb, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register);
+                let borrow = self.flags.carry;
+                self.a = self.subtract(value, borrow);
+            }
+            (Mnemonic::Ana, &[Operand::Reg(register)]) => {
+                let value = self.register(bus, register);
+                self.and(value);
+            }
+            (Mnemonic::Xra, &[Operand::Reg(register)]) => {
+                let value = self.a ^ self.register(bus, register);
+                self.logic(value);
+            }
+            (Mnemonic::Ora, &[Operand::Reg(register)]) => {
+                let value = self.a | self.register(bus, register);
+                self.logic(value);
+            }
+            (Mnemonic::Cmp, &[Operand::Reg(register)]) => {
+                // A subtraction that only keeps the flags.
+                let value = self.register(bus, register);
+                self.subtract(value, false);
+            }
+            (Mnemonic::Adi, &[Operand::Byte(value)]) => self.a = self.add(value, false),
+            (Mnemonic::Aci, &[Operand::Byte(value)]) => {
+                let carry = self.flags.carry;
+                self.a = self.add(value, carry);
+            }
+            (Mnemonic::Sui, &[Operand::Byte(value)]) => self.a = self.subtract(value, false),
+            (Mnemonic::Sbi, &[Operand::Byte(value)]) => {
+                let borrow = self.flags.carry;
+                self.a = self.subtract(value, borrow);
+            }
+            (Mnemonic::Ani, &[Operand::Byte(value)]) => self.and(value),
+            (Mnemonic::Xri, &[Operand::Byte(value)]) => {
+                let value = self.a ^ value;
+                self.logic(value);
+            }
+            (Mnemonic::Ori, &[Operand::Byte(value)]) => {
+                let value = self.a | value;
+                self.logic(value);
+            }
+            (Mnemonic::Cpi, &[Operand::Byte(value)]) => {
+                self.subtract(value, false);
+            }
+            (Mnemonic::Pop, &[Operand::Pair(pair)]) => {
+                let value = self.pop(bus);
+                self.set_pair(pair, value);
+            }
+            (Mnemonic::Push, &[Operand::Pair(pair)]) => {
+                let value = self.pair(pair);
+                self.push(bus, value);
+            }
+            (Mnemonic::Rst, &[Operand::Vector(number)]) => {
+                let pc = self.pc;
+                self.push(bus, pc);
+                self.pc = u16::from(number) * 8;
+            }
+            (Mnemonic::Ret, _) => self.pc = self.pop(bus),
+            (Mnemonic::Jmp, &[Operand::Address(address)]) => self.pc = address,
+            (Mnemonic::Call, &[Operand::Address(address)]) => {
+                let pc = self.pc;
+                self.push(bus, pc);
+                self.pc = address;
+            }
+            (Mnemonic::Out, &[Operand::Port(port)]) => bus.output(port, self.a),
+            (Mnemonic::In, &[Operand::Port(port)]) => self.a = bus.input(port),
+            (Mnemonic::Xthl, _) => {
+                let value = read_word(bus, self.sp);
+                let hl = self.hl();
+                write_word(bus, self.sp, hl);
+                self.set_pair(RegisterPair::H, value);
+            }
+            (Mnemonic::Pchl, _) => self.pc = self.hl(),
+            (Mnemonic::Xchg, _) => {
+                let (de, hl) = (self.de(), self.hl());
+                self.set_pair(RegisterPair::D, hl);
+                self.set_pair(RegisterPair::H, de);
+            }
+            (Mnemonic::Sphl, _) => self.sp = self.hl(),
+            (Mnemonic::Di, _) => self.interrupts_enabled = false,
+            (Mnemonic::Ei, _) => {
+                self.interrupts_enabled = true;
+                self.interrupt_delay = true;
+            }
+            // Everything left is a conditional jump, call or return.
+            _ => match instruction.flow() {
+                Flow::ConditionalJump(address) => {
+                    if self.condition(mnemonic) {
+                        self.pc = address;
+                    }
+                }
+                Flow::ConditionalCall(address) => {
+                    if self.condition(mnemonic) {
+                        let pc = self.pc;
+                        self.push(bus, pc);
+                        self.pc = address;
+                        return true;
+                    }
+                }
+                Flow::ConditionalReturn => {
+                    if self.condition(mnemonic) {
+                        self.pc = self.pop(bus);
+                        return true;
+                    }
+                }
+                _ => unreachable!("decoder produced {:?}", instruction),
+            },
+        }
+        false
+    }
+}
+
+fn read_word<B: Bus>(bus: &mut B, address: u16) -> u16 {
+    u16::from(bus.read(address)) | u16::from(bus.read(address.wrapping_add(1))) << 8
+}
+
+fn write_word<B: Bus>(bus: &mut B, address: u16, value: u16) {
+    bus.write(address, value as u8);
+    bus.write(address.wrapping_add(1), (value >> 8) as u8);
+}
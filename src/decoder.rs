@@ -0,0 +1,1363 @@
+use instruction::{Instruction, Mnemonic, Operand, Register, RegisterPair};
+use std::error::Error;
+use std::fmt;
+
+// Every one of the 256 opcodes decodes to something, so the only thing
+// that can go wrong is running out of bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    // There were no bytes at all to decode.
+    Empty,
+    // The opcode needs more operand bytes than are left, like a JMP in the
+    // last two bytes of a ROM dump.
+    Truncated {
+        address: u16,
+        opcode: u8,
+        length: u8,
+        available: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Empty => write!(f, "No bytes left to decode"),
+            DecodeError::Truncated {
+                address,
+                opcode,
+                length,
+                available,
+            } => write!(
+                f,
+                "Truncated instruction {:02x} at {:x}: needs {} bytes but only {} left",
+                opcode, address, length, available
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+// Decodes the instruction at the start of `bytes`, which lives at `address` in memory.
+// Any operand bytes have to be in the slice after the opcode, otherwise you get
+// back DecodeError::Truncated.
+pub fn decode(bytes: &[u8], address: u16) -> Result<Instruction, DecodeError> {
+    let opcode = match bytes.first() {
+        Some(&opcode) => opcode,
+        None => return Err(DecodeError::Empty),
+    };
+    // Decode against a zero padded copy so we can find out how long the
+    // instruction should be before deciding whether it fits.
+    let mut padded = [0; 3];
+    let available = bytes.len().min(padded.len());
+    padded[..available].copy_from_slice(&bytes[..available]);
+    let instruction = decode_opcode(&padded, address);
+    if instruction.length as usize > bytes.len() {
+        return Err(DecodeError::Truncated {
+            address,
+            opcode,
+            length: instruction.length,
+            available: bytes.len(),
+        });
+    }
+    Ok(instruction)
+}
+
+fn decode_opcode(bytes: &[u8; 3], address: u16) -> Instruction {
+    let opcode = bytes[0];
+    let byte = || bytes[1];
+    // 16 bit data is stored low byte first.
+    let word = || u16::from(bytes[2]) << 8 | u16::from(bytes[1]);
+    let undocumented = |mnemonic: Mnemonic, operands: Vec<Operand>| {
+        let mut instruction = Instruction::new(address, opcode, mnemonic, operands);
+        instruction.undocumented = true;
+        instruction
+    };
+    let instruction = |mnemonic: Mnemonic, operands: Vec<Operand>| {
+        Instruction::new(address, opcode, mnemonic, operands)
+    };
+    match opcode {
+        0x00 => instruction(Mnemonic::Nop, vec![]),
+        0x01 => {
+            // Load byte 3 into register B. Load byte 2 into register C.
+            instruction(
+                Mnemonic::Lxi,
+                vec![Operand::Pair(RegisterPair::B), Operand::Word(word())],
+            )
+        }
+        0x02 => {
+            // Store A indirect
+            // The content of register A is moved to the memory location whose address is in the register pair rp.
+            // Note: only register pairs rp=B (registers B and C) or rp=D (registers D and E) may be specified.
+            instruction(Mnemonic::Stax, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0x03 => {
+            // BC <- BC + 1
+            instruction(Mnemonic::Inx, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0x04 => {
+            // Increment B
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::B)])
+        }
+        0x05 => {
+            // Decrement B
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::B)])
+        }
+        0x06 => {
+            // Move immediate second byte to register B
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::B), Operand::Byte(byte())],
+            )
+        }
+        0x07 => {
+            // Rotate left.
+            // The content of the accumulator is rotated left one position.
+            // The low order bit and the CY flag are both set to the value shifted out of the high order bit position.
+            // Only the CY flag is affected.
+            instruction(Mnemonic::Rlc, vec![])
+        }
+        0x08 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x09 => {
+            // HL = HL + BC
+            instruction(Mnemonic::Dad, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0x0a => {
+            // Load B indirect
+            instruction(Mnemonic::Ldax, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0x0b => {
+            // Decrement Register BC
+            instruction(Mnemonic::Dcx, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0x0c => {
+            // C <- C + 1;
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::C)])
+        }
+        0x0d => {
+            // Decrement C.
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::C)])
+        }
+        0x0e => {
+            // Move byte 2 into register C.
+            // MVI stands for Move Immediate.
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::C), Operand::Byte(byte())],
+            )
+        }
+        0x0f => {
+            // The content of the accumulator is rotated right one position.
+            // The high order bit and the CY flag are both set to the value shifted out of the low order bit position. Only the CY flag is affected.
+            instruction(Mnemonic::Rrc, vec![])
+        }
+        0x10 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x11 => {
+            // Move byte 3 into register D. Move byte 2 into register E.
+            // LXI stands for Load Immediate Register
+            instruction(
+                Mnemonic::Lxi,
+                vec![Operand::Pair(RegisterPair::D), Operand::Word(word())],
+            )
+        }
+        0x12 => {
+            // A <- (DE)
+            instruction(Mnemonic::Stax, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0x13 => {
+            // Increment register DE
+            instruction(Mnemonic::Inx, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0x14 => {
+            // Increment Register D
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::D)])
+        }
+        0x15 => {
+            // Decrement D
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::D)])
+        }
+        0x16 => {
+            // Move byte 2 into D
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::D), Operand::Byte(byte())],
+            )
+        }
+        0x17 => {
+            // Rotate A left through carry
+            // A = A << 1; bit 0 = prev CY; CY = prev bit 7
+            instruction(Mnemonic::Ral, vec![])
+        }
+        0x18 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x19 => {
+            // Add D & E to H & L
+            instruction(Mnemonic::Dad, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0x1a => {
+            // Load A indirect
+            // The content of the memory location, whose address is in the register pair rp, is moved to register A.
+            // Note: only register pairs rp=B (registers B and C·) or rp=D (registers D and E) may be specified.
+            instruction(Mnemonic::Ldax, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0x1b => {
+            // DE = DE - 1
+            instruction(Mnemonic::Dcx, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0x1c => {
+            // E <- E + 1
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::E)])
+        }
+        0x1d => {
+            // E <- E - 1
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::E)])
+        }
+        0x1e => {
+            // move byte 2 into E
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::E), Operand::Byte(byte())],
+            )
+        }
+        0x1f => {
+            // A = A >> 1; bit 7 = prev bit 7; CY = prev bit 0
+            // Rotate A right thru carry
+            instruction(Mnemonic::Rar, vec![])
+        }
+        0x20 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x21 => {
+            // Load immediate register
+            // LXI rp, data 16
+            // Byte 3 of the instruction is moved into the high order register of the register pair rp.
+            // byte 2 of the instruction is moved into the low order register (rl) of the restier pair rp.
+            instruction(
+                Mnemonic::Lxi,
+                vec![Operand::Pair(RegisterPair::H), Operand::Word(word())],
+            )
+        }
+        0x22 => {
+            // Store H & L direct
+            // The content of register L is moved to the memory location whose address is specified in byte 2 and byte 3.
+            // The content of register H is moved to the next memory location.
+            // ((byte 3) (byte 2)) <- (L)
+            // ((byte 3)(byte 2) + 1) <- (H)
+            instruction(Mnemonic::Shld, vec![Operand::Address(word())])
+        }
+        0x23 => {
+            // Increment H & L registers
+            instruction(Mnemonic::Inx, vec![Operand::Pair(RegisterPair::H)])
+        }
+        0x24 => {
+            // Increment H register.
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::H)])
+        }
+        0x25 => {
+            // H <- H - 1
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::H)])
+        }
+        0x26 => {
+            // Move byte into register H.
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::H), Operand::Byte(byte())],
+            )
+        }
+        0x27 => {
+            // Decimal Adjust A(ccumulator)
+            // The eight-bit number in the accumulator is adjusted to form two four-bit Binary-Coded-Decimal digits by the following process:
+            // If the value of the least significant 4 bits of the accumulator is greater than 9 or if the AC flag is set, 6 is added to the accumulator.
+            // If the value of the most significant 4 bits of the accumulator is now greater than 9, or if the CY flag is set, 6 is added to the most significant 4 bits of the accumulator.
+            instruction(Mnemonic::Daa, vec![])
+        }
+        0x28 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x29 => {
+            // Adds HI to HL.
+            instruction(Mnemonic::Dad, vec![Operand::Pair(RegisterPair::H)])
+        }
+        0x2a => {
+            // Load H & L direct
+            // The content of the memory location, whose address is specified in byte 2 and byte 3 of the instruction, is moved to register L.
+            // The content of the memory location at the succeeding address is moved to register H.
+            instruction(Mnemonic::Lhld, vec![Operand::Address(word())])
+        }
+        0x2b => {
+            // Decrement H & L
+            instruction(Mnemonic::Dcx, vec![Operand::Pair(RegisterPair::H)])
+        }
+        0x2c => {
+            // Increment Register L
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::L)])
+        }
+        0x2d => {
+            // Decrement L
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::L)])
+        }
+        0x2e => {
+            // Move byte 2 into register L
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::L), Operand::Byte(byte())],
+            )
+        }
+        0x2f => {
+            // Compliment A
+            // The contents of the accumulator are complemented- (zero bits become 1, one bits become 0).
+            // A <- !A
+            instruction(Mnemonic::Cma, vec![])
+        }
+        0x30 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x31 => {
+            // move byte 3 into high order location of register SP. move byte 2 into low order location of register SP.
+            instruction(
+                Mnemonic::Lxi,
+                vec![Operand::Pair(RegisterPair::SP), Operand::Word(word())],
+            )
+        }
+        0x32 => {
+            // Store A direct.
+            // The content of the accumulator is moved to the next two bytes.
+            instruction(Mnemonic::Sta, vec![Operand::Address(word())])
+        }
+        0x33 => {
+            // SP <- SP + 1
+            instruction(Mnemonic::Inx, vec![Operand::Pair(RegisterPair::SP)])
+        }
+        0x34 => {
+            // Increment Register M (HL)
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::M)])
+        }
+        0x35 => {
+            // Decrements Register M.
+            // The content of the memory location whose address is contained in the H and L registers is decremented by one.
+            // Sets the flags 	Z, S, P, AC
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::M)])
+        }
+        0x36 => {
+            // Move second byte, into register HL.
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::M), Operand::Byte(byte())],
+            )
+        }
+        0x37 => {
+            // Set carry
+            // Sets carry flag to 1
+            instruction(Mnemonic::Stc, vec![])
+        }
+        0x38 => {
+            // Undocumented, does nothing like NOP
+            undocumented(Mnemonic::Nop, vec![])
+        }
+        0x39 => {
+            // HL = HL + SP
+            instruction(Mnemonic::Dad, vec![Operand::Pair(RegisterPair::SP)])
+        }
+        0x3a => {
+            // Load Accumulator Direct
+            // The content of the memory location, whose address is specified in byte 2 and byte 3 of the instruction, is moved to register A.
+            instruction(Mnemonic::Lda, vec![Operand::Address(word())])
+        }
+        0x3b => {
+            // SP <- SP - 1
+            instruction(Mnemonic::Dcx, vec![Operand::Pair(RegisterPair::SP)])
+        }
+        0x3c => {
+            // Increment Register A
+            instruction(Mnemonic::Inr, vec![Operand::Reg(Register::A)])
+        }
+        0x3d => {
+            // Decrement Register A
+            instruction(Mnemonic::Dcr, vec![Operand::Reg(Register::A)])
+        }
+        0x3e => {
+            // Move immediate register
+            instruction(
+                Mnemonic::Mvi,
+                vec![Operand::Reg(Register::A), Operand::Byte(byte())],
+            )
+        }
+        0x3f => {
+            // CY=!CY
+            instruction(Mnemonic::Cmc, vec![])
+        }
+        0x40 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::B), Operand::Reg(Register::B)],
+        ),
+        0x41 => {
+            // Move C into B.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::B), Operand::Reg(Register::C)],
+            )
+        }
+        0x42 => {
+            // B <- D
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::B), Operand::Reg(Register::D)],
+            )
+        }
+        0x43 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::B), Operand::Reg(Register::E)],
+        ),
+        0x44 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::B), Operand::Reg(Register::H)],
+        ),
+        0x45 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::B), Operand::Reg(Register::L)],
+        ),
+        0x46 => {
+            // Move the contents of HL to B
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::B), Operand::Reg(Register::M)],
+            )
+        }
+        0x47 => {
+            // Move contents of A to B.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::B), Operand::Reg(Register::A)],
+            )
+        }
+        0x48 => {
+            // Move B into C.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::C), Operand::Reg(Register::B)],
+            )
+        }
+        0x49 => {
+            // C <- C
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::C), Operand::Reg(Register::C)],
+            )
+        }
+        0x4a => {
+            // C <- D
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::C), Operand::Reg(Register::D)],
+            )
+        }
+        0x4b => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::C), Operand::Reg(Register::E)],
+        ),
+        0x4c => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::C), Operand::Reg(Register::H)],
+        ),
+        0x4d => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::C), Operand::Reg(Register::L)],
+        ),
+        0x4e => {
+            // Move HL to C
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::C), Operand::Reg(Register::M)],
+            )
+        }
+        0x4f => {
+            // Move A to C.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::C), Operand::Reg(Register::A)],
+            )
+        }
+        0x50 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::D), Operand::Reg(Register::B)],
+        ),
+        0x51 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::D), Operand::Reg(Register::C)],
+        ),
+        0x52 => {
+            // Move D to itself, does nothing
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::D)],
+            )
+        }
+        0x53 => {
+            // Move E to D
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::E)],
+            )
+        }
+        0x54 => {
+            // Move H into D
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::H)],
+            )
+        }
+        0x55 => {
+            // Move L to D
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::L)],
+            )
+        }
+        0x56 => {
+            // Move register HL into register D.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::M)],
+            )
+        }
+        0x57 => {
+            // Move D into A.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::D), Operand::Reg(Register::A)],
+            )
+        }
+        0x59 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::E), Operand::Reg(Register::C)],
+        ),
+        0x5b => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::E), Operand::Reg(Register::E)],
+        ),
+        0x58 => {
+            // Move B to E
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::B)],
+            )
+        }
+        0x5a => {
+            // Move D to E
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::D)],
+            )
+        }
+        0x5c => {
+            // Move H to E
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::H)],
+            )
+        }
+        0x5d => {
+            // Move L to E
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::L)],
+            )
+        }
+        0x5e => {
+            // Move register HL to register E.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::M)],
+            )
+        }
+        0x5f => {
+            // Move contents of A to E.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::E), Operand::Reg(Register::A)],
+            )
+        }
+        0x60 => {
+            // Move B to H
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::B)],
+            )
+        }
+        0x61 => {
+            // Move contents of register C to register H
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::C)],
+            )
+        }
+        0x62 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::H), Operand::Reg(Register::D)],
+        ),
+        0x63 => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::H), Operand::Reg(Register::E)],
+        ),
+        0x64 => {
+            // Move H to H (??)
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::H)],
+            )
+        }
+        0x65 => {
+            // H <- L
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::L)],
+            )
+        }
+        0x66 => {
+            // Move memory (HL) to register H
+            // H <- (HL)
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::M)],
+            )
+        }
+        0x67 => {
+            // Move register A to register H
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::H), Operand::Reg(Register::A)],
+            )
+        }
+        0x68 => {
+            // Move register B to register A.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::B)],
+            )
+        }
+        0x69 => {
+            // Move value of C into register L.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::C)],
+            )
+        }
+        0x6a => {
+            // Move D to L
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::D)],
+            )
+        }
+        0x6b => {
+            // Move E to L
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::E)],
+            )
+        }
+        0x6c => {
+            // L <- H
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::H)],
+            )
+        }
+        0x6d => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::L), Operand::Reg(Register::L)],
+        ),
+        0x6e => instruction(
+            Mnemonic::Mov,
+            vec![Operand::Reg(Register::L), Operand::Reg(Register::M)],
+        ),
+        0x6f => {
+            // Move memory (L) to register A
+            // L <- A
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::L), Operand::Reg(Register::A)],
+            )
+        }
+        0x70 => {
+            // Move register B to register M (HL);
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::B)],
+            )
+        }
+        0x71 => {
+            // Move register C into register M.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::C)],
+            )
+        }
+        0x72 => {
+            // Move register D into register M(HL);
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::D)],
+            )
+        }
+        0x73 => {
+            // Move register E to register M (HL)
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::E)],
+            )
+        }
+        0x74 => {
+            // (HL) <- H
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::H)],
+            )
+        }
+        0x75 => {
+            // Move L to memory address (HL)
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::L)],
+            )
+        }
+        0x76 => {
+            // HALT (??)
+            // The processor is stopped.
+            instruction(Mnemonic::Hlt, vec![])
+        }
+        0x77 => {
+            // Move register A to register HL.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::M), Operand::Reg(Register::A)],
+            )
+        }
+        0x78 => {
+            // Move contents of register B to register A.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::B)],
+            )
+        }
+        0x79 => {
+            // Move register C to A.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::C)],
+            )
+        }
+        0x7a => {
+            // Move contents of register D to register A
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::D)],
+            )
+        }
+        0x7b => {
+            // Move contents of E to A
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::E)],
+            )
+        }
+        0x7c => {
+            // Move register H into register A.
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::H)],
+            )
+        }
+        0x7d => {
+            // Move contents of L to A
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::L)],
+            )
+        }
+        0x7e => {
+            // Move memory to register
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::M)],
+            )
+        }
+        0x7f => {
+            // A <- A
+            instruction(
+                Mnemonic::Mov,
+                vec![Operand::Reg(Register::A), Operand::Reg(Register::A)],
+            )
+        }
+        0x80 => {
+            // Add value of B to the accumulator to A
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::B)])
+        }
+        0x81 => {
+            // A <- A + C
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::C)])
+        }
+        0x82 => {
+            // A <- A + D
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::D)])
+        }
+        0x83 => {
+            // Add E to A.
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::E)])
+        }
+        0x84 => {
+            // A <- A + H;
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::H)])
+        }
+        0x85 => {
+            // Adds register L to register A.
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::L)])
+        }
+        0x86 => {
+            // Add M (HL) to register A.
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::M)])
+        }
+        0x87 => {
+            // Add A to A
+            instruction(Mnemonic::Add, vec![Operand::Reg(Register::A)])
+        }
+        0x88 => {
+            // A <- A + B + CY
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::B)])
+        }
+        0x89 => {
+            // Add C to A with carry
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::C)])
+        }
+        0x8a => {
+            // Add D, and CY to A.
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::D)])
+        }
+        0x8b => {
+            // A <- A + E + CY
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::E)])
+        }
+        0x8c => {
+            // Add H to A with carry
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::H)])
+        }
+        0x8d => {
+            // Add L to A with carry
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::L)])
+        }
+        0x8e => {
+            // A <- A + (HL) + CY
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::M)])
+        }
+        0x8f => {
+            // Add A to A with carry
+            instruction(Mnemonic::Adc, vec![Operand::Reg(Register::A)])
+        }
+        0x90 => {
+            // A <- A + B
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::B)])
+        }
+        0x91 => {
+            // Subtract C from A
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::C)])
+        }
+        0x92 => {
+            // Subtract D from A
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::D)])
+        }
+        0x93 => {
+            // Subtract E from A
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::E)])
+        }
+        0x94 => {
+            // A <- A + H
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::H)])
+        }
+        0x95 => {
+            // Subtract L from A
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::L)])
+        }
+        0x96 => {
+            // Subtract memory (HL) from A
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::M)])
+        }
+        0x97 => {
+            // Subtract value of A from A (i guess sets A to 0)
+            instruction(Mnemonic::Sub, vec![Operand::Reg(Register::A)])
+        }
+        0x98 => {
+            // A <- A - B - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::B)])
+        }
+        0x99 => {
+            // A <- A - C - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::C)])
+        }
+        0x9a => {
+            // A <- A - D - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::D)])
+        }
+        0x9b => {
+            // A <- A - E - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::E)])
+        }
+        0x9c => {
+            // Subtract H from A with borrow
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::H)])
+        }
+        0x9d => {
+            // A <- A - L - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::L)])
+        }
+        0x9e => {
+            // A <- A - (HL) - CY
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::M)])
+        }
+        0x9f => {
+            // Subtract A from A with borrow
+            instruction(Mnemonic::Sbb, vec![Operand::Reg(Register::A)])
+        }
+        0xa0 => {
+            // Does an and of A & B on A
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::B)])
+        }
+        0xa1 => {
+            // And C with A
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::C)])
+        }
+        0xa2 => {
+            // And D with A
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::D)])
+        }
+        0xa3 => {
+            // A <- A * E
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::E)])
+        }
+        0xa4 => {
+            // And H with A
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::H)])
+        }
+        0xa5 => {
+            // And L with A
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::L)])
+        }
+        0xa6 => {
+            // A <- A & (HL)
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::M)])
+        }
+        0xa7 => {
+            // AND register
+            // The content of register r is logically anded with the content of the accumulator.
+            // The result is placed in the accumulator. The CY flag is cleared.
+            instruction(Mnemonic::Ana, vec![Operand::Reg(Register::A)])
+        }
+        0xa8 => {
+            // A <- A ^ B
+            // Exclusive OR
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::B)])
+        }
+        0xa9 => {
+            // Exclusive Or C with A
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::C)])
+        }
+        0xaa => {
+            // A <- A ^ D
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::D)])
+        }
+        0xab => {
+            // Exclusive Or E with A
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::E)])
+        }
+        0xac => {
+            // Exclusive Or H with A
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::H)])
+        }
+        0xad => {
+            // Exclusive Or L with A
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::L)])
+        }
+        0xae => {
+            // Exclusive Or memory (HL) with A
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::M)])
+        }
+        0xaf => {
+            // Exclusive OR Register
+            // The content of register r is exclusive-or'd with the content of the accumulator. The result is placed in the accumulator. The CY and AC flags are cleared.
+            instruction(Mnemonic::Xra, vec![Operand::Reg(Register::A)])
+        }
+        0xb0 => {
+            // A <- A | B
+            // Or register with A.
+            // Does an OR with register B on register A.
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::B)])
+        }
+        0xb1 => {
+            // Or C with A
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::C)])
+        }
+        0xb2 => {
+            // Or D with A
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::D)])
+        }
+        0xb3 => {
+            // A <- A | E
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::E)])
+        }
+        0xb4 => {
+            // A <- A | H
+            // Or register H with register A.
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::H)])
+        }
+        0xb5 => {
+            // Or L with A
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::L)])
+        }
+        0xb6 => {
+            // Does an or with whats in register M (HL) versus accumulator
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::M)])
+        }
+        0xb7 => {
+            // Or A with A
+            instruction(Mnemonic::Ora, vec![Operand::Reg(Register::A)])
+        }
+        0xb8 => {
+            // Compare register B to A.
+            // Contents of register B are substracted from A.
+            // couldnt tell you why its called compare
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::B)])
+        }
+        0xb9 => {
+            // Compare C with A
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::C)])
+        }
+        0xba => {
+            // Compare D with A
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::D)])
+        }
+        0xbb => {
+            // A - E
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::E)])
+        }
+        0xbc => {
+            // A - H
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::H)])
+        }
+        0xbd => {
+            // Compare L with A
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::L)])
+        }
+        0xbe => {
+            // A - (HL)
+            // Subtract HL from A.
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::M)])
+        }
+        0xbf => {
+            // Compare A with A
+            instruction(Mnemonic::Cmp, vec![Operand::Reg(Register::A)])
+        }
+        0xc0 => {
+            // If NZ (non-zero), RET (return).
+            instruction(Mnemonic::Rnz, vec![])
+        }
+        0xc1 => {
+            // Pop register pair B & C off stack
+            instruction(Mnemonic::Pop, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0xc2 => {
+            // Jump on no zero.
+            // not sure, but i think its jump if a check is non zero.
+            instruction(Mnemonic::Jnz, vec![Operand::Address(word())])
+        }
+        0xc3 => {
+            // JMP, uses next two bytes to determine where to jump to.
+            instruction(Mnemonic::Jmp, vec![Operand::Address(word())])
+        }
+        0xc4 => {
+            // Call address if non zero
+            // Not sure what it checks? whats non zero
+            instruction(Mnemonic::Cnz, vec![Operand::Address(word())])
+        }
+        0xc5 => {
+            // Push register Pair B & C on stack
+            instruction(Mnemonic::Push, vec![Operand::Pair(RegisterPair::B)])
+        }
+        0xc6 => {
+            // Add immediate to A.
+            // The content of the second byte of the instruction is added to the content of the accumulator.
+            // The result is placed in the accumulator.
+            instruction(Mnemonic::Adi, vec![Operand::Byte(byte())])
+        }
+        0xc7 => {
+            // CALL $0
+            instruction(Mnemonic::Rst, vec![Operand::Vector(0)])
+        }
+        0xc8 => {
+            // If Z, RET
+            // if what is zero??
+            instruction(Mnemonic::Rz, vec![])
+        }
+        0xc9 => {
+            // Return
+            // The content of the memory location whose address is specified in register SP is moved to the low-order eight bits of register PC.
+            // The content of the memory location whose address is one more than the content of register SP is moved to the high-order eight bits of register PC. The content of register SP is incremented by 2.
+            instruction(Mnemonic::Ret, vec![])
+        }
+        0xca => {
+            // Jump on zero
+            // not sure. i imagine it checks to see if something is 0, then jumps...
+            instruction(Mnemonic::Jz, vec![Operand::Address(word())])
+        }
+        0xcb => {
+            // Undocumented, behaves exactly like JMP
+            undocumented(Mnemonic::Jmp, vec![Operand::Address(word())])
+        }
+        0xcc => {
+            // If Z, call adr
+            // Z is one of the flags. Must check if that flag is set then calls.
+            instruction(Mnemonic::Cz, vec![Operand::Address(word())])
+        }
+        0xcd => {
+            // The high-order eight bits of the next instruction address are moved to the memory location whose address is one less than the content of register SP.
+            // The low-order eight bits of the next instruction address are moved to the memory location whose address is two less than the content of register SP.
+            // The content of register SP is decremented by 2. Control is transferred to the instruction whose address is specified in byte 3 and byte 2 of the current instruction.
+            instruction(Mnemonic::Call, vec![Operand::Address(word())])
+        }
+        0xce => {
+            // Add immediate to A with carry
+            instruction(Mnemonic::Aci, vec![Operand::Byte(byte())])
+        }
+        0xcf => {
+            // CALL $8
+            instruction(Mnemonic::Rst, vec![Operand::Vector(1)])
+        }
+        0xd0 => {
+            // if NCY, RET
+            // return on no carry. if the carry flag hasn't been set, return.
+            instruction(Mnemonic::Rnc, vec![])
+        }
+        0xd1 => {
+            // Pop register pair D & E off stack
+            instruction(Mnemonic::Pop, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0xd2 => {
+            // Jump if no carry
+            // not sure, but i imagine it's jump if the no carry flag hasn't been set.
+            instruction(Mnemonic::Jnc, vec![Operand::Address(word())])
+        }
+        0xd3 => {
+            // Output
+            // The content of register A is placed on the eight bit bi-directional data bus for transmission to the specified port.
+            instruction(Mnemonic::Out, vec![Operand::Port(byte())])
+        }
+        0xd4 => {
+            // If NCY (no carry) call address.
+            instruction(Mnemonic::Cnc, vec![Operand::Address(word())])
+        }
+        0xd5 => {
+            // Push register Pair D & E on stack
+            instruction(Mnemonic::Push, vec![Operand::Pair(RegisterPair::D)])
+        }
+        0xd6 => {
+            // Subtract immediate from A
+            // Subtracts the byte value from A.
+            instruction(Mnemonic::Sui, vec![Operand::Byte(byte())])
+        }
+        0xd7 => {
+            // CALL $10
+            instruction(Mnemonic::Rst, vec![Operand::Vector(2)])
+        }
+        0xd8 => {
+            // if carry flag is set, return.
+            instruction(Mnemonic::Rc, vec![])
+        }
+        0xd9 => {
+            // Undocumented, behaves exactly like RET
+            undocumented(Mnemonic::Ret, vec![])
+        }
+        0xda => {
+            // Conditional Jump
+            // If the specified condition is true, control is transferred to the instruction whose address is specified in byte 3 and byte 2 of the current instruction; other- wise, control continues sequentially.
+            instruction(Mnemonic::Jc, vec![Operand::Address(word())])
+        }
+        0xdb => {
+            // IN port
+            // The data placed on the eight bit bi-directional data bus by the specified port is moved to register A.
+            instruction(Mnemonic::In, vec![Operand::Port(byte())])
+        }
+        0xdc => {
+            // if CY, CALL adr
+            instruction(Mnemonic::Cc, vec![Operand::Address(word())])
+        }
+        0xdd => {
+            // Undocumented, behaves exactly like CALL
+            undocumented(Mnemonic::Call, vec![Operand::Address(word())])
+        }
+        0xde => {
+            // Subtract immediate from A with borrow
+            // The contents of the second byte of the instruction and the contents of the CY flag are both subtracted from the accumulator.
+            // The result is placed in the accumulator.
+            instruction(Mnemonic::Sbi, vec![Operand::Byte(byte())])
+        }
+        0xdf => {
+            // CALL $18
+            instruction(Mnemonic::Rst, vec![Operand::Vector(3)])
+        }
+        0xe0 => {
+            // if PO, RET
+            instruction(Mnemonic::Rpo, vec![])
+        }
+        0xe1 => {
+            // Pop register pair H & L off stack
+            instruction(Mnemonic::Pop, vec![Operand::Pair(RegisterPair::H)])
+        }
+        0xe2 => {
+            // Jump on parity odd
+            instruction(Mnemonic::Jpo, vec![Operand::Address(word())])
+        }
+        0xe3 => {
+            // Exchange top of stack, H & L.
+            // The content of the L register is exchanged with the content of the memory location whose address is specified by the content of register SP.
+            // The content of the H register is exchanged with the content of the memory location whose address is one more than the content of register SP.
+            instruction(Mnemonic::Xthl, vec![])
+        }
+        0xe4 => {
+            // if PO, CALL adr
+            // Call on parity odd
+            instruction(Mnemonic::Cpo, vec![Operand::Address(word())])
+        }
+        0xe5 => {
+            // Push register Pair H & L on stack
+            instruction(Mnemonic::Push, vec![Operand::Pair(RegisterPair::H)])
+        }
+        0xe6 => {
+            // And immediate with A.
+            // The content of the second byte of the instruction is logically anded with the contents of the accumulator.
+            // The result is placed in the accumulator. The CY and AC flags are cleared.
+            instruction(Mnemonic::Ani, vec![Operand::Byte(byte())])
+        }
+        0xe7 => {
+            // CALL $20
+            instruction(Mnemonic::Rst, vec![Operand::Vector(4)])
+        }
+        0xe8 => {
+            // if PE, RET
+            // Return on parity even
+            instruction(Mnemonic::Rpe, vec![])
+        }
+        0xe9 => {
+            // H & L to program counter.
+            // The content of register H is moved to the high-order eight bits of register PC.
+            // The content of register l is moved to the low-order eight bits of register PC.
+            instruction(Mnemonic::Pchl, vec![])
+        }
+        0xea => {
+            // if PE, PC <- adr
+            // Jump on parity even
+            instruction(Mnemonic::Jpe, vec![Operand::Address(word())])
+        }
+        0xeb => {
+            // Exchange D & E, H& L Registers
+            instruction(Mnemonic::Xchg, vec![])
+        }
+        0xec => instruction(Mnemonic::Cpe, vec![Operand::Address(word())]),
+        0xed => {
+            // Undocumented, behaves exactly like CALL
+            undocumented(Mnemonic::Call, vec![Operand::Address(word())])
+        }
+        0xee => {
+            // Exclusive Or immediate with A
+            instruction(Mnemonic::Xri, vec![Operand::Byte(byte())])
+        }
+        0xef => {
+            // CALL $28
+            instruction(Mnemonic::Rst, vec![Operand::Vector(5)])
+        }
+        0xf0 => {
+            // if P, RET
+            instruction(Mnemonic::Rp, vec![])
+        }
+        0xf1 => {
+            // Pop A and Flags off stack
+            instruction(Mnemonic::Pop, vec![Operand::Pair(RegisterPair::PSW)])
+        }
+        0xf2 => {
+            // if P, PC <- adr
+            // Jump on plus, which is when S = 0
+            instruction(Mnemonic::Jp, vec![Operand::Address(word())])
+        }
+        0xf3 => {
+            // Disable interrupts
+            // The interrupt system is disabled immediately following the execution of the DI instruction.
+            instruction(Mnemonic::Di, vec![])
+        }
+        0xf4 => {
+            // if P, CALL adr
+            instruction(Mnemonic::Cp, vec![Operand::Address(word())])
+        }
+        0xf5 => {
+            // PSW means "processor state word", adds 1 to accumulator.
+            // Push A and Flags on stack
+            instruction(Mnemonic::Push, vec![Operand::Pair(RegisterPair::PSW)])
+        }
+        0xf6 => {
+            // does an or of A and the byte data, loads that into the accumulator.
+            instruction(Mnemonic::Ori, vec![Operand::Byte(byte())])
+        }
+        0xf7 => {
+            // CALL $30
+            instruction(Mnemonic::Rst, vec![Operand::Vector(6)])
+        }
+        0xf8 => {
+            // if M, return
+            instruction(Mnemonic::Rm, vec![])
+        }
+        0xf9 => {
+            // Move HL to SP
+            // The contents of registers H and L (16 bits) are moved to register SP.
+            instruction(Mnemonic::Sphl, vec![])
+        }
+        0xfa => {
+            // if M, PC <- adr
+            // Jump on Minus
+            // ok, cool. you can see this in 4-11
+            // minus is if S = 1
+            // S is a flag that stands for Sign
+            instruction(Mnemonic::Jm, vec![Operand::Address(word())])
+        }
+        0xfb => {
+            // Enable interrupts
+            // The interrupt system is enabled following the execu- tion of the next instruction.
+            instruction(Mnemonic::Ei, vec![])
+        }
+        0xfc => {
+            // if M, CALL adr
+            instruction(Mnemonic::Cm, vec![Operand::Address(word())])
+        }
+        0xfd => {
+            // Undocumented, behaves exactly like CALL
+            undocumented(Mnemonic::Call, vec![Operand::Address(word())])
+        }
+        0xfe => {
+            // Compare immediate
+            // The content of the second byte of the instruction is subtracted from the accumulator.
+            // The condition flags are set by the result of the subtraction.
+            // The Z flag is set to 1 if (A) = (byte 2). The CY flag is set to 1 if (A) <(byte 2).
+            instruction(Mnemonic::Cpi, vec![Operand::Byte(byte())])
+        }
+        0xff => {
+            // CALL $38
+            instruction(Mnemonic::Rst, vec![Operand::Vector(7)])
+        }
+    }
+}
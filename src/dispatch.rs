@@ -0,0 +1,303 @@
+// Works out where a PCHL goes. The simple case is `LXI H,adr / PCHL`, but
+// most programs dispatch through a table of addresses:
+//
+//     LXI H,TABLE
+//     DAD D          index * 2 in DE
+//     MOV E,M
+//     INX H
+//     MOV D,M
+//     XCHG
+//     PCHL
+//
+// Rather than match that exact sequence, the instructions before the PCHL
+// are run with what's known about each register: a constant, a pointer
+// somewhere into a table (the index isn't known), or the low or high byte of
+// one of the table's entries. If H and L end up as the two halves of an
+// entry, the PCHL goes through the table.
+
+use image::Image;
+use instruction::{Flow, Instruction, Mnemonic, Operand, Register, RegisterPair};
+use listing::Line;
+use std::collections::BTreeMap;
+
+// Stop reading a table after this many entries, whatever it looks like.
+const MAX_ENTRIES: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Address(u16),
+    // A table of addresses starting here, one of which gets used.
+    Table(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Unknown,
+    Byte(u8),
+    // Both halves of a pair that points into the table at this address,
+    // this many bytes into an entry.
+    Pointer(u16, u8),
+    // A byte read from an entry in the table at this address.
+    Low(u16),
+    High(u16),
+}
+
+// What's in a register pair, put back together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Word {
+    Constant(u16),
+    Pointer(u16, u8),
+    // A whole entry from the table.
+    Entry(u16),
+}
+
+// B, C, D, E, H, L, (M), A, the same order as `Register`.
+struct State {
+    registers: [Value; 8],
+}
+
+impl State {
+    fn get(&self, register: Register) -> Value {
+        match register {
+            Register::M => self.memory(),
+            _ => self.registers[register as usize],
+        }
+    }
+
+    fn set(&mut self, register: Register, value: Value) {
+        self.registers[register as usize] = value;
+    }
+
+    fn halves(pair: RegisterPair) -> Option<(Register, Register)> {
+        match pair {
+            RegisterPair::B => Some((Register::B, Register::C)),
+            RegisterPair::D => Some((Register::D, Register::E)),
+            RegisterPair::H => Some((Register::H, Register::L)),
+            _ => None,
+        }
+    }
+
+    fn pair(&self, pair: RegisterPair) -> Option<Word> {
+        let (high, low) = State::halves(pair)?;
+        match (self.get(high), self.get(low)) {
+            (Value::Byte(high), Value::Byte(low)) => {
+                Some(Word::Constant(u16::from(high) << 8 | u16::from(low)))
+            }
+            (Value::Pointer(table, offset), low) if low == Value::Pointer(table, offset) => {
+                Some(Word::Pointer(table, offset))
+            }
+            (Value::High(table), Value::Low(other)) if table == other => Some(Word::Entry(table)),
+            _ => None,
+        }
+    }
+
+    fn set_pair(&mut self, pair: RegisterPair, word: Option<Word>) {
+        let (high, low) = match State::halves(pair) {
+            Some(halves) => halves,
+            None => return,
+        };
+        let (high_value, low_value) = match word {
+            Some(Word::Constant(value)) => {
+                (Value::Byte((value >> 8) as u8), Value::Byte(value as u8))
+            }
+            Some(Word::Pointer(table, offset)) => {
+                (Value::Pointer(table, offset), Value::Pointer(table, offset))
+            }
+            Some(Word::Entry(table)) => (Value::High(table), Value::Low(table)),
+            None => (Value::Unknown, Value::Unknown),
+        };
+        self.set(high, high_value);
+        self.set(low, low_value);
+    }
+
+    // What M reads as, going by where H and L point.
+    fn memory(&self) -> Value {
+        match self.pair(RegisterPair::H) {
+            Some(Word::Pointer(table, 0)) => Value::Low(table),
+            Some(Word::Pointer(table, 1)) => Value::High(table),
+            _ => Value::Unknown,
+        }
+    }
+
+    fn step(&mut self, instruction: &Instruction) {
+        match (instruction.mnemonic, &instruction.operands[..]) {
+            (Mnemonic::Lxi, &[Operand::Pair(pair), Operand::Word(value)]) => {
+                self.set_pair(pair, Some(Word::Constant(value)))
+            }
+            (Mnemonic::Mvi, &[Operand::Reg(Register::M), _]) => {}
+            (Mnemonic::Mvi, &[Operand::Reg(register), Operand::Byte(value)]) => {
+                self.set(register, Value::Byte(value))
+            }
+            (Mnemonic::Mov, &[Operand::Reg(Register::M), _]) => {}
+            (Mnemonic::Mov, &[Operand::Reg(to), Operand::Reg(from)]) => {
+                let value = self.get(from);
+                self.set(to, value);
+            }
+            (Mnemonic::Dad, &[Operand::Pair(pair)]) => {
+                let sum = match (self.pair(RegisterPair::H), self.pair(pair)) {
+                    (Some(Word::Constant(a)), Some(Word::Constant(b))) => {
+                        Some(Word::Constant(a.wrapping_add(b)))
+                    }
+                    // Doubling an index, or whatever it was, isn't a table.
+                    _ if pair == RegisterPair::H => None,
+                    // A table plus an index nobody knows.
+                    (Some(Word::Constant(table)), _) | (_, Some(Word::Constant(table))) => {
+                        Some(Word::Pointer(table, 0))
+                    }
+                    (Some(Word::Pointer(table, offset)), _) => Some(Word::Pointer(table, offset)),
+                    _ => None,
+                };
+                self.set_pair(RegisterPair::H, sum);
+            }
+            (Mnemonic::Inx, &[Operand::Pair(pair)]) => {
+                let next = match self.pair(pair) {
+                    Some(Word::Constant(value)) => Some(Word::Constant(value.wrapping_add(1))),
+                    Some(Word::Pointer(table, offset)) => Some(Word::Pointer(table, offset + 1)),
+                    _ => None,
+                };
+                self.set_pair(pair, next);
+            }
+            (Mnemonic::Dcx, &[Operand::Pair(pair)]) => {
+                let previous = match self.pair(pair) {
+                    Some(Word::Constant(value)) => Some(Word::Constant(value.wrapping_sub(1))),
+                    Some(Word::Pointer(table, offset)) if offset > 0 => {
+                        Some(Word::Pointer(table, offset - 1))
+                    }
+                    _ => None,
+                };
+                self.set_pair(pair, previous);
+            }
+            (Mnemonic::Xchg, _) => {
+                let (d, e) = (self.get(Register::D), self.get(Register::E));
+                let (h, l) = (self.get(Register::H), self.get(Register::L));
+                self.set(Register::D, h);
+                self.set(Register::E, l);
+                self.set(Register::H, d);
+                self.set(Register::L, e);
+            }
+            (Mnemonic::Inr, &[Operand::Reg(register)])
+            | (Mnemonic::Dcr, &[Operand::Reg(register)]) => {
+                if register != Register::M {
+                    self.set(register, Value::Unknown);
+                }
+            }
+            (Mnemonic::Pop, &[Operand::Pair(RegisterPair::PSW)]) => {
+                self.set(Register::A, Value::Unknown)
+            }
+            (Mnemonic::Pop, &[Operand::Pair(pair)]) => self.set_pair(pair, None),
+            (Mnemonic::Lhld, _) | (Mnemonic::Xthl, _) => self.set_pair(RegisterPair::H, None),
+            // These only change A, if anything.
+            (Mnemonic::Add, _)
+            | (Mnemonic::Adc, _)
+            | (Mnemonic::Sub, _)
+            | (Mnemonic::Sbb, _)
+            | (Mnemonic::Ana, _)
+            | (Mnemonic::Xra, _)
+            | (Mnemonic::Ora, _)
+            | (Mnemonic::Adi, _)
+            | (Mnemonic::Aci, _)
+            | (Mnemonic::Sui, _)
+            | (Mnemonic::Sbi, _)
+            | (Mnemonic::Ani, _)
+            | (Mnemonic::Xri, _)
+            | (Mnemonic::Ori, _)
+            | (Mnemonic::Rlc, _)
+            | (Mnemonic::Rrc, _)
+            | (Mnemonic::Ral, _)
+            | (Mnemonic::Rar, _)
+            | (Mnemonic::Cma, _)
+            | (Mnemonic::Daa, _)
+            | (Mnemonic::In, _)
+            | (Mnemonic::Lda, _)
+            | (Mnemonic::Ldax, _) => self.set(Register::A, Value::Unknown),
+            (Mnemonic::Cmp, _)
+            | (Mnemonic::Cpi, _)
+            | (Mnemonic::Sta, _)
+            | (Mnemonic::Stax, _)
+            | (Mnemonic::Shld, _)
+            | (Mnemonic::Push, _)
+            | (Mnemonic::Out, _)
+            | (Mnemonic::Sphl, _)
+            | (Mnemonic::Stc, _)
+            | (Mnemonic::Cmc, _)
+            | (Mnemonic::Nop, _)
+            | (Mnemonic::Di, _)
+            | (Mnemonic::Ei, _) => {}
+            // Anything else could have changed anything.
+            _ => self.registers = [Value::Unknown; 8],
+        }
+    }
+}
+
+// Where a PCHL goes after these instructions, which run straight through
+// into it.
+pub fn resolve(instructions: &[Instruction]) -> Option<Target> {
+    let mut state = State {
+        registers: [Value::Unknown; 8],
+    };
+    for instruction in instructions {
+        state.step(instruction);
+    }
+    match state.pair(RegisterPair::H)? {
+        Word::Constant(address) => Some(Target::Address(address)),
+        Word::Entry(table) => Some(Target::Table(table)),
+        Word::Pointer(..) => None,
+    }
+}
+
+// Where each PCHL in the listing goes, if that can be worked out from the
+// instructions that run straight into it.
+pub fn pchl_targets(lines: &[Line]) -> BTreeMap<u16, Target> {
+    let mut targets = BTreeMap::new();
+    let mut run: Vec<Instruction> = Vec::new();
+    for line in lines {
+        let instruction = match *line {
+            Line::Instruction(ref instruction) => instruction,
+            Line::Data { .. } => {
+                run.clear();
+                continue;
+            }
+        };
+        match instruction.flow() {
+            Flow::Next => run.push(instruction.clone()),
+            Flow::Indirect => {
+                if let Some(target) = resolve(&run) {
+                    targets.insert(instruction.address, target);
+                }
+                run.clear();
+            }
+            _ => run.clear(),
+        }
+    }
+    targets
+}
+
+// The addresses in the table at `start`. Nothing says how long a table is,
+// so it stops at the first entry that points outside the image, runs into
+// code, or reaches the lowest address the entries so far point to after the
+// table, which is usually where the first of the routines it's for starts.
+pub fn entries<F: Fn(u16) -> bool>(image: &Image, start: u16, is_code: F) -> Vec<u16> {
+    let mut entries = Vec::new();
+    let mut first_after = u32::MAX;
+    let mut position = u32::from(start);
+    while entries.len() < MAX_ENTRIES && position + 1 < first_after && position < 0xffff {
+        let address = position as u16;
+        if is_code(address) || is_code(address + 1) {
+            break;
+        }
+        let entry = match (image.byte(address), image.byte(address + 1)) {
+            (Some(low), Some(high)) => u16::from(high) << 8 | u16::from(low),
+            _ => break,
+        };
+        let inside = u32::from(entry) >= u32::from(start) && u32::from(entry) <= position + 1;
+        if !image.contains(entry) || inside {
+            break;
+        }
+        if entry > start {
+            first_after = first_after.min(u32::from(entry));
+        }
+        entries.push(entry);
+        position += 2;
+    }
+    entries
+}
@@ -0,0 +1,127 @@
+// Reads Intel HEX files, the text format assemblers and EPROM programmers
+// use. Each line is a record like `:10010000214601360121470136007EFE09D2190140`:
+// byte count, address, record type, data and a checksum.
+
+use image::Image;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexError {
+    // 1 based line number in the file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for HexError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexFile {
+    pub image: Image,
+    // From a start address record (type 03 or 05), if there was one.
+    pub start: Option<u16>,
+}
+
+// Intel HEX is all colons, hex digits and line breaks, and starts with a colon.
+pub fn is_hex(bytes: &[u8]) -> bool {
+    let first = bytes.iter().find(|byte| !byte.is_ascii_whitespace());
+    first == Some(&b':')
+        && bytes
+            .iter()
+            .all(|&byte| byte == b':' || byte.is_ascii_hexdigit() || byte.is_ascii_whitespace())
+}
+
+pub fn parse(text: &str) -> Result<HexFile, HexError> {
+    let mut image = Image::new();
+    let mut start = None;
+    // Set by extended segment (02) and extended linear (04) records.
+    let mut base: u32 = 0;
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| HexError {
+            line: index + 1,
+            message,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = match line.strip_prefix(':') {
+            Some(digits) => decode_hex(digits).map_err(&error)?,
+            None => return Err(error("Record doesn't start with a colon".to_string())),
+        };
+        if record.len() < 5 || record.len() != 5 + record[0] as usize {
+            return Err(error("Record is the wrong length".to_string()));
+        }
+        let checksum = record.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        if checksum != 0 {
+            return Err(error(format!(
+                "Bad checksum, expected {:02X}",
+                record[record.len() - 1].wrapping_sub(checksum)
+            )));
+        }
+        let offset = u32::from(record[1]) << 8 | u32::from(record[2]);
+        let data = &record[4..record.len() - 1];
+        match record[3] {
+            0x00 => {
+                // In u64 since a 04 record can put the base near the top of
+                // a u32.
+                let address = u64::from(base) + u64::from(offset);
+                if address + data.len() as u64 > 0x10000 {
+                    return Err(error(format!(
+                        "Data at {:x} is past the 8080's 64K of memory",
+                        address
+                    )));
+                }
+                image
+                    .add(address as u16, data)
+                    .map_err(|problem| error(problem.to_string()))?;
+            }
+            0x01 => break,
+            0x02 => base = word(data).map_err(&error)? << 4,
+            0x04 => base = word(data).map_err(&error)? << 16,
+            0x03 | 0x05 => {
+                if data.len() != 4 {
+                    return Err(error(
+                        "Start address record is the wrong length".to_string(),
+                    ));
+                }
+                // Segment:offset for 03, a linear address for 05. Either way
+                // the 8080 only cares about the bottom 16 bits.
+                let address = if record[3] == 0x03 {
+                    (u32::from(data[0]) << 12 | u32::from(data[1]) << 4)
+                        + (u32::from(data[2]) << 8 | u32::from(data[3]))
+                } else {
+                    u32::from(data[2]) << 8 | u32::from(data[3])
+                };
+                start = Some(address as u16);
+            }
+            kind => return Err(error(format!("Unknown record type {:02X}", kind))),
+        }
+    }
+    Ok(HexFile { image, start })
+}
+
+fn word(data: &[u8]) -> Result<u32, String> {
+    if data.len() != 2 {
+        return Err("Address record is the wrong length".to_string());
+    }
+    Ok(u32::from(data[0]) << 8 | u32::from(data[1]))
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err("Record isn't made of hex digit pairs".to_string());
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| {
+            u8::from_str_radix(&text[index..index + 2], 16).map_err(|error| error.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+// The 8080's 64K address space, with only the parts something was loaded
+// into filled in. A raw binary is one block at its origin, but an Intel HEX
+// file can put bytes anywhere.
+
+use std::error::Error;
+use std::fmt;
+
+const ADDRESS_SPACE: usize = 0x10000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub start: u16,
+    pub bytes: Vec<u8>,
+}
+
+impl Region {
+    // One past the last address, which can be 0x10000 so it isn't a u16.
+    pub fn end(&self) -> usize {
+        self.start as usize + self.bytes.len()
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        address >= self.start && (address as usize) < self.end()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageError {
+    // Something was already loaded at this address.
+    Overlap(u16),
+    // The bytes would run off the top of memory.
+    TooBig { start: u16, length: usize },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageError::Overlap(address) => write!(f, "{:x} is loaded more than once", address),
+            ImageError::TooBig { start, length } => write!(
+                f,
+                "{} bytes at {:x} goes past the end of memory",
+                length, start
+            ),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+// Regions are kept in address order, and ones that touch are joined up so
+// an instruction can run from one record into the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Image {
+    regions: Vec<Region>,
+}
+
+impl Image {
+    pub fn new() -> Image {
+        Image::default()
+    }
+
+    pub fn from_binary(bytes: &[u8], origin: u16) -> Result<Image, ImageError> {
+        let mut image = Image::new();
+        image.add(origin, bytes)?;
+        Ok(image)
+    }
+
+    pub fn add(&mut self, start: u16, bytes: &[u8]) -> Result<(), ImageError> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let end = start as usize + bytes.len();
+        if end > ADDRESS_SPACE {
+            return Err(ImageError::TooBig {
+                start,
+                length: bytes.len(),
+            });
+        }
+        for region in &self.regions {
+            if (start as usize) < region.end() && end > region.start as usize {
+                return Err(ImageError::Overlap(start.max(region.start)));
+            }
+        }
+        let index = self
+            .regions
+            .iter()
+            .position(|region| region.start > start)
+            .unwrap_or(self.regions.len());
+        self.regions.insert(
+            index,
+            Region {
+                start,
+                bytes: bytes.to_vec(),
+            },
+        );
+        self.join_touching();
+        Ok(())
+    }
+
+    fn join_touching(&mut self) {
+        let mut joined: Vec<Region> = Vec::new();
+        for region in self.regions.drain(..) {
+            match joined.last_mut() {
+                Some(last) if last.end() == region.start as usize => {
+                    last.bytes.extend(region.bytes)
+                }
+                _ => joined.push(region),
+            }
+        }
+        self.regions = joined;
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        self.region(address).is_some()
+    }
+
+    pub fn region(&self, address: u16) -> Option<&Region> {
+        self.regions.iter().find(|region| region.contains(address))
+    }
+
+    pub fn byte(&self, address: u16) -> Option<u8> {
+        self.region(address)
+            .map(|region| region.bytes[(address - region.start) as usize])
+    }
+
+    // Everything loaded from `address` up to the end of its region, which is
+    // what the decoder wants to see.
+    pub fn bytes_from(&self, address: u16) -> Option<&[u8]> {
+        self.region(address)
+            .map(|region| &region.bytes[(address - region.start) as usize..])
+    }
+}
@@ -0,0 +1,350 @@
+use labels::Labels;
+use platform::Direction;
+use std::fmt;
+use syntax::Syntax;
+
+// The eight single registers as they're encoded in the opcode.
+// M isn't a real register, it's the memory location pointed to by H & L.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    M,
+    A,
+}
+
+// Register pairs. B means BC, D means DE, H means HL.
+// PSW (A and the flags) only shows up in PUSH and POP, SP never does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterPair {
+    B,
+    D,
+    H,
+    SP,
+    PSW,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mnemonic {
+    Nop,
+    Lxi,
+    Stax,
+    Inx,
+    Inr,
+    Dcr,
+    Mvi,
+    Rlc,
+    Dad,
+    Ldax,
+    Dcx,
+    Rrc,
+    Ral,
+    Rar,
+    Shld,
+    Daa,
+    Lhld,
+    Cma,
+    Sta,
+    Stc,
+    Lda,
+    Cmc,
+    Mov,
+    Hlt,
+    Add,
+    Adc,
+    Sub,
+    Sbb,
+    Ana,
+    Xra,
+    Ora,
+    Cmp,
+    Rnz,
+    Pop,
+    Jnz,
+    Jmp,
+    Cnz,
+    Push,
+    Adi,
+    Rst,
+    Rz,
+    Ret,
+    Jz,
+    Cz,
+    Call,
+    Aci,
+    Rnc,
+    Jnc,
+    Out,
+    Cnc,
+    Sui,
+    Rc,
+    Jc,
+    In,
+    Cc,
+    Sbi,
+    Rpo,
+    Jpo,
+    Xthl,
+    Cpo,
+    Ani,
+    Rpe,
+    Pchl,
+    Jpe,
+    Xchg,
+    Cpe,
+    Xri,
+    Rp,
+    Jp,
+    Di,
+    Cp,
+    Ori,
+    Rm,
+    Sphl,
+    Jm,
+    Ei,
+    Cm,
+    Cpi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Reg(Register),
+    Pair(RegisterPair),
+    // Immediate data byte, e.g. MVI B or ADI.
+    Byte(u8),
+    // Immediate data word, only LXI has one of these.
+    Word(u16),
+    // A memory address, either somewhere to jump to or somewhere to load/store.
+    Address(u16),
+    // I/O port number for IN and OUT.
+    Port(u8),
+    // RST number, 0 to 7. The call goes to 8 times this.
+    Vector(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u16,
+    pub opcode: u8,
+    pub mnemonic: Mnemonic,
+    pub operands: Vec<Operand>,
+    pub length: u8,
+    // Set for the opcodes Intel never documented, like 0x08 (NOP) or 0xcb (JMP).
+    // They still do something on a real chip, but assemblers won't produce them.
+    pub undocumented: bool,
+}
+
+impl Instruction {
+    pub fn new(
+        address: u16,
+        opcode: u8,
+        mnemonic: Mnemonic,
+        operands: Vec<Operand>,
+    ) -> Instruction {
+        // Every instruction is the opcode byte plus whatever data the operands carry.
+        let length = 1 + operands.iter().map(|operand| operand.size()).sum::<u8>();
+        Instruction {
+            address,
+            opcode,
+            mnemonic,
+            operands,
+            length,
+            undocumented: false,
+        }
+    }
+}
+
+impl Operand {
+    // How many bytes of the instruction this operand takes up after the opcode.
+    pub fn size(&self) -> u8 {
+        match *self {
+            Operand::Byte(_) | Operand::Port(_) => 1,
+            Operand::Word(_) | Operand::Address(_) => 2,
+            Operand::Reg(_) | Operand::Pair(_) | Operand::Vector(_) => 0,
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Register::B => "B",
+            Register::C => "C",
+            Register::D => "D",
+            Register::E => "E",
+            Register::H => "H",
+            Register::L => "L",
+            Register::M => "M",
+            Register::A => "A",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for RegisterPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            RegisterPair::B => "B",
+            RegisterPair::D => "D",
+            RegisterPair::H => "H",
+            RegisterPair::SP => "SP",
+            RegisterPair::PSW => "PSW",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The Debug name is the mnemonic in title case, so just shout it.
+        f.write_str(&format!("{:?}", self).to_uppercase())
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&Syntax::default().operand(self))
+    }
+}
+
+impl fmt::Display for Instruction {
+    // Prints as `c3: JMP #18d4`, the opcode then the instruction.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+// An instruction that prints label names in place of the addresses they stand for.
+pub struct Labelled<'a> {
+    instruction: &'a Instruction,
+    labels: &'a Labels,
+}
+
+impl<'a> fmt::Display for Labelled<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.instruction.write(f, Some(self.labels))
+    }
+}
+
+impl Instruction {
+    // Prints as `c3: JMP L_18D4` when 18d4 has a label.
+    pub fn with_labels<'a>(&'a self, labels: &'a Labels) -> Labelled<'a> {
+        Labelled {
+            instruction: self,
+            labels,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, labels: Option<&Labels>) -> fmt::Result {
+        write!(
+            f,
+            "{:02x}: {}",
+            self.opcode,
+            Syntax::default().instruction(self, labels)
+        )
+    }
+
+    // The label to print in place of this operand, if there is one.
+    pub fn label_for<'a>(&self, operand: &Operand, labels: &'a Labels) -> Option<&'a str> {
+        match *operand {
+            Operand::Address(address) => labels.get(address),
+            // LXI H is how PCHL gets its destination, so it can point at code too.
+            Operand::Word(value) if self.operands[0] == Operand::Pair(RegisterPair::H) => {
+                labels.get(value)
+            }
+            // Anything else loaded into a pair could just be a number, so
+            // only names someone chose for that address get used.
+            Operand::Word(value) => labels.symbol(value),
+            Operand::Port(number) if self.mnemonic == Mnemonic::In => {
+                labels.port(Direction::In, number)
+            }
+            Operand::Port(number) => labels.port(Direction::Out, number),
+            _ => None,
+        }
+    }
+}
+
+// Where execution can go after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    // Carries on to the next instruction.
+    Next,
+    Jump(u16),
+    // Either jumps to the address or carries on.
+    ConditionalJump(u16),
+    // Goes to the address and then comes back to the next instruction. RST is one of these too.
+    Call(u16),
+    ConditionalCall(u16),
+    Return,
+    // Either returns or carries on.
+    ConditionalReturn,
+    // PCHL, the destination is whatever is in H & L at the time.
+    Indirect,
+}
+
+impl Instruction {
+    // The address straight after this instruction.
+    pub fn next_address(&self) -> u16 {
+        self.address.wrapping_add(u16::from(self.length))
+    }
+
+    pub fn flow(&self) -> Flow {
+        // Only jumps, calls and RSTs read this, and they always have a destination.
+        let target = self
+            .operands
+            .iter()
+            .filter_map(|operand| match *operand {
+                Operand::Address(address) => Some(address),
+                Operand::Vector(number) => Some(u16::from(number) * 8),
+                _ => None,
+            })
+            .next()
+            .unwrap_or(0);
+        match self.mnemonic {
+            Mnemonic::Jmp => Flow::Jump(target),
+            Mnemonic::Jnz
+            | Mnemonic::Jz
+            | Mnemonic::Jnc
+            | Mnemonic::Jc
+            | Mnemonic::Jpo
+            | Mnemonic::Jpe
+            | Mnemonic::Jp
+            | Mnemonic::Jm => Flow::ConditionalJump(target),
+            Mnemonic::Call | Mnemonic::Rst => Flow::Call(target),
+            Mnemonic::Cnz
+            | Mnemonic::Cz
+            | Mnemonic::Cnc
+            | Mnemonic::Cc
+            | Mnemonic::Cpo
+            | Mnemonic::Cpe
+            | Mnemonic::Cp
+            | Mnemonic::Cm => Flow::ConditionalCall(target),
+            Mnemonic::Ret => Flow::Return,
+            Mnemonic::Rnz
+            | Mnemonic::Rz
+            | Mnemonic::Rnc
+            | Mnemonic::Rc
+            | Mnemonic::Rpo
+            | Mnemonic::Rpe
+            | Mnemonic::Rp
+            | Mnemonic::Rm => Flow::ConditionalReturn,
+            Mnemonic::Pchl => Flow::Indirect,
+            _ => Flow::Next,
+        }
+    }
+
+    // The jump or call destination, if this instruction has one.
+    pub fn target(&self) -> Option<u16> {
+        match self.flow() {
+            Flow::Jump(target)
+            | Flow::ConditionalJump(target)
+            | Flow::Call(target)
+            | Flow::ConditionalCall(target) => Some(target),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// Runs Space Invaders. The board is an 8080 at 2MHz with 8K of ROM, 8K of
+// RAM (the top 7K of it is the screen) and a shift register chip the game
+// uses to draw sprites at any bit position. The screen is fired at 60 times
+// a second, with RST 1 halfway down and RST 2 at the bottom.
+
+use cpu::{Bus, Cpu};
+use image::Image;
+
+pub const WIDTH: usize = 224;
+pub const HEIGHT: usize = 256;
+const MEMORY: usize = 0x4000;
+const ROM_END: u16 = 0x2000;
+const VIDEO_RAM: usize = 0x2400;
+const CYCLES_PER_FRAME: u32 = 2_000_000 / 60;
+
+// The memory and ports the game sees.
+pub struct Board {
+    memory: Vec<u8>,
+    // IN 0, 1 and 2: the buttons, coin slot and DIP switches. Set bits
+    // to press things.
+    pub inputs: [u8; 3],
+    // The last values written to OUT 3 and OUT 5.
+    pub sound: [u8; 2],
+    shift: u16,
+    shift_offset: u8,
+}
+
+impl Board {
+    fn new(image: &Image) -> Board {
+        let mut memory = vec![0; MEMORY];
+        for region in image.regions() {
+            for (address, &byte) in (region.start as usize..).zip(&region.bytes) {
+                if address < MEMORY {
+                    memory[address] = byte;
+                }
+            }
+        }
+        Board {
+            memory,
+            // Bits that always read as 1 on a real board.
+            inputs: [0x0e, 0x08, 0x00],
+            sound: [0; 2],
+            shift: 0,
+            shift_offset: 0,
+        }
+    }
+}
+
+impl Bus for Board {
+    // Only 14 address lines are connected, so everything above 0x4000 is
+    // the same memory again.
+    fn read(&mut self, address: u16) -> u8 {
+        self.memory[address as usize % MEMORY]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let address = address as usize % MEMORY;
+        if address >= ROM_END as usize {
+            self.memory[address] = value;
+        }
+    }
+
+    fn input(&mut self, port: u8) -> u8 {
+        match port {
+            0..=2 => self.inputs[port as usize],
+            // The shift register hands back 8 bits from its 16, starting
+            // however far in OUT 2 said.
+            3 => (self.shift >> (8 - self.shift_offset)) as u8,
+            _ => 0,
+        }
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            2 => self.shift_offset = value & 7,
+            3 => self.sound[0] = value,
+            // New bytes go in the top and push the old ones down.
+            4 => self.shift = u16::from(value) << 8 | self.shift >> 8,
+            5 => self.sound[1] = value,
+            _ => {}
+        }
+    }
+}
+
+pub struct Invaders {
+    pub cpu: Cpu,
+    pub board: Board,
+    pub frames: u64,
+}
+
+impl Invaders {
+    // Takes the ROMs already loaded at 0x0000, like invaders.h, .g, .f and .e.
+    pub fn new(image: &Image) -> Invaders {
+        Invaders {
+            cpu: Cpu::new(),
+            board: Board::new(image),
+            frames: 0,
+        }
+    }
+
+    // Runs one 60th of a second.
+    pub fn run_frame(&mut self) {
+        for &interrupt in &[1, 2] {
+            let mut cycles = 0;
+            while cycles < CYCLES_PER_FRAME / 2 {
+                cycles += self.cpu.step(&mut self.board);
+            }
+            self.cpu.interrupt(&mut self.board, interrupt);
+        }
+        self.frames += 1;
+    }
+
+    // What's on the screen, one byte per pixel (0 or 255) from the top left.
+    // The monitor is on its side, so each byte of video RAM is 8 pixels going
+    // up the screen, and each 32 bytes is a column starting from the left.
+    pub fn screen(&self) -> Vec<u8> {
+        let mut pixels = vec![0; WIDTH * HEIGHT];
+        let video = &self.board.memory[VIDEO_RAM..];
+        for (index, &byte) in video.iter().enumerate() {
+            let x = index / 32;
+            let bottom = (index % 32) * 8;
+            for bit in 0..8 {
+                if byte & 1 << bit != 0 {
+                    let y = HEIGHT - 1 - (bottom + bit);
+                    pixels[y * WIDTH + x] = 0xff;
+                }
+            }
+        }
+        pixels
+    }
+}
@@ -0,0 +1,214 @@
+// The listing as JSON, for tools that would otherwise have to pick apart the
+// text. Either one document with every line in it, or JSON Lines (one object
+// a line) for streaming. The schema is in the README under "JSON output";
+// anything that changes what's already there means a new VERSION, adding
+// fields doesn't.
+
+use blocks;
+use instruction::{Flow, Instruction, Operand};
+use labels::Labels;
+use listing::{self, DataFormat, Line};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    // {"format": ..., "version": 1, "lines": [...]}
+    Document,
+    // The header object, then each line's object on a line of its own.
+    Lines,
+}
+
+// `comments` are anything to say about a line beyond what the object already
+// has, by address: BDOS calls, port descriptions, map notes and so on.
+pub fn write<W: Write>(
+    out: &mut W,
+    lines: &[Line],
+    labels: &Labels,
+    comments: &BTreeMap<u16, Vec<String>>,
+    style: Style,
+) -> io::Result<()> {
+    // Where each PCHL can go, which is whatever comes after its block.
+    let indirect: BTreeMap<u16, Vec<u16>> = blocks::blocks(lines)
+        .values()
+        .filter(|block| block.last().flow() == Flow::Indirect)
+        .map(|block| {
+            let targets = block.successors.iter().map(|&(to, _)| to).collect();
+            (block.last().address, targets)
+        })
+        .collect();
+    let header = format!(
+        "\"format\":\"eightyeighty-disassembler\",\"version\":{}",
+        VERSION
+    );
+    let objects = lines.iter().map(|line| {
+        let no_comments = Vec::new();
+        let comments = comments.get(&line.address()).unwrap_or(&no_comments);
+        match *line {
+            Line::Instruction(ref instruction) => {
+                let targets = indirect
+                    .get(&instruction.address)
+                    .map_or(&[][..], |targets| &targets[..]);
+                instruction_object(instruction, labels, comments, targets)
+            }
+            Line::Data { .. } => data_object(line, labels, comments),
+        }
+    });
+    match style {
+        Style::Document => {
+            writeln!(out, "{{{},\"lines\":[", header)?;
+            for (index, object) in objects.enumerate() {
+                let comma = if index + 1 < lines.len() { "," } else { "" };
+                writeln!(out, "{}{}", object, comma)?;
+            }
+            writeln!(out, "]}}")
+        }
+        Style::Lines => {
+            writeln!(out, "{{{}}}", header)?;
+            for object in objects {
+                writeln!(out, "{}", object)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn instruction_object(
+    instruction: &Instruction,
+    labels: &Labels,
+    comments: &[String],
+    indirect: &[u16],
+) -> String {
+    let mut bytes = vec![instruction.opcode];
+    for operand in &instruction.operands {
+        match *operand {
+            Operand::Byte(value) | Operand::Port(value) => bytes.push(value),
+            Operand::Word(value) | Operand::Address(value) => {
+                bytes.push(value as u8);
+                bytes.push((value >> 8) as u8);
+            }
+            _ => {}
+        }
+    }
+    let operands: Vec<String> = instruction
+        .operands
+        .iter()
+        .map(|operand| {
+            let (kind, value) = match *operand {
+                Operand::Reg(register) => ("register", string(&register.to_string())),
+                Operand::Pair(pair) => ("pair", string(&pair.to_string())),
+                Operand::Byte(value) => ("byte", value.to_string()),
+                Operand::Word(value) => ("word", value.to_string()),
+                Operand::Address(value) => ("address", value.to_string()),
+                Operand::Port(value) => ("port", value.to_string()),
+                Operand::Vector(value) => ("vector", value.to_string()),
+            };
+            let label = instruction.label_for(operand, labels).map(string);
+            format!(
+                "{{\"type\":\"{}\",\"value\":{},\"label\":{}}}",
+                kind,
+                value,
+                optional(label)
+            )
+        })
+        .collect();
+    let next = instruction.next_address();
+    let (kind, targets) = match instruction.flow() {
+        Flow::Next => ("next", vec![]),
+        Flow::Jump(target) => ("jump", vec![target]),
+        Flow::ConditionalJump(target) => ("conditional_jump", vec![target, next]),
+        Flow::Call(target) => ("call", vec![target]),
+        Flow::ConditionalCall(target) => ("conditional_call", vec![target, next]),
+        Flow::Return => ("return", vec![]),
+        Flow::ConditionalReturn => ("conditional_return", vec![next]),
+        Flow::Indirect => ("indirect", indirect.to_vec()),
+    };
+    let timing = instruction.timing();
+    format!(
+        "{{\"type\":\"instruction\",\"address\":{},\"bytes\":{},\"length\":{},\"label\":{},\
+         \"mnemonic\":\"{}\",\"operands\":[{}],\"cycles\":{},\"cycles_taken\":{},\
+         \"flow\":{{\"kind\":\"{}\",\"targets\":{}}},\"undocumented\":{},\"comments\":{}}}",
+        instruction.address,
+        numbers(&bytes),
+        instruction.length,
+        optional(labels.get(instruction.address).map(string)),
+        instruction.mnemonic,
+        operands.join(","),
+        timing.cycles,
+        optional(timing.taken.map(|taken| taken.to_string())),
+        kind,
+        numbers(&targets),
+        instruction.undocumented,
+        strings(comments)
+    )
+}
+
+fn data_object(line: &Line, labels: &Labels, comments: &[String]) -> String {
+    let (address, bytes, format, warning) = match *line {
+        Line::Data {
+            address,
+            ref bytes,
+            format,
+            ref warning,
+            ..
+        } => (address, bytes, format, warning),
+        Line::Instruction(_) => unreachable!(),
+    };
+    let (format, values) = match format {
+        DataFormat::Bytes => ("bytes", numbers(bytes)),
+        DataFormat::Words => ("words", numbers(&listing::words(bytes))),
+        DataFormat::Pointers => ("pointers", numbers(&listing::words(bytes))),
+        DataFormat::Text => {
+            let text: String = bytes.iter().map(|&byte| byte as char).collect();
+            ("text", string(&text))
+        }
+    };
+    format!(
+        "{{\"type\":\"data\",\"address\":{},\"bytes\":{},\"length\":{},\"label\":{},\
+         \"format\":\"{}\",\"values\":{},\"warning\":{},\"comments\":{}}}",
+        address,
+        numbers(bytes),
+        bytes.len(),
+        optional(labels.get(address).map(string)),
+        format,
+        values,
+        optional(warning.as_ref().map(|warning| string(warning))),
+        strings(comments)
+    )
+}
+
+fn numbers<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn strings(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| string(value)).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_string())
+}
+
+// A JSON string, quotes and all.
+fn string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                quoted.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
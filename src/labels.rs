@@ -0,0 +1,153 @@
+// Names for the places the code jumps and calls to, so the listing can say
+// `JMP L_18D4` and mark the line at 18d4 rather than leaving readers to
+// piece the address together from the bytes.
+
+use dispatch::{self, Target};
+use instruction::Flow;
+use listing::{self, DataFormat, Line};
+use platform::Direction;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Labels {
+    names: BTreeMap<u16, String>,
+    // Names from a symbol file, which win over the generated ones.
+    symbols: BTreeMap<u16, String>,
+    // Names for I/O ports, from the platform.
+    ports: BTreeMap<(Direction, u8), String>,
+}
+
+impl Labels {
+    pub fn new() -> Labels {
+        Labels::default()
+    }
+
+    pub fn get(&self, address: u16) -> Option<&str> {
+        self.symbols
+            .get(&address)
+            .or_else(|| self.names.get(&address))
+            .map(|name| name.as_str())
+    }
+
+    // Only the names someone gave us, not the generated ones.
+    pub fn symbol(&self, address: u16) -> Option<&str> {
+        self.symbols.get(&address).map(|name| name.as_str())
+    }
+
+    // The first name given for an address wins, the same as within a symbol
+    // file, so an earlier --symbols file isn't overridden by a later one. A
+    // name can only be used for one address though, or the asm output would
+    // define it twice.
+    pub fn insert_symbol(&mut self, address: u16, name: String) -> Result<(), String> {
+        let upper = name.to_uppercase();
+        if let Some((&other, _)) = self
+            .symbols
+            .iter()
+            .find(|&(&other, existing)| other != address && existing.to_uppercase() == upper)
+        {
+            return Err(format!("{} is already {:04x}", name, other));
+        }
+        self.symbols.entry(address).or_insert(name);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, address: u16, name: String) {
+        self.names.insert(address, name);
+    }
+
+    pub fn port(&self, direction: Direction, number: u8) -> Option<&str> {
+        self.ports
+            .get(&(direction, number))
+            .map(|name| name.as_str())
+    }
+
+    pub fn insert_port(&mut self, direction: Direction, number: u8, name: String) {
+        self.ports.insert((direction, number), name);
+    }
+
+    // Every named address in order, symbols and generated labels together.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> {
+        let addresses: BTreeSet<u16> = self
+            .names
+            .keys()
+            .chain(self.symbols.keys())
+            .cloned()
+            .collect();
+        addresses
+            .into_iter()
+            .filter_map(move |address| Some((address, self.get(address)?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.symbols.is_empty()
+    }
+}
+
+// Finds every jump, call and RST destination in the listing, and every
+// address in a pointer table, and names it. Calls get SUB_ and everything
+// else gets L_. Destinations that don't land on
+// the start of a line are skipped since there'd be nowhere to print the label.
+pub fn generate(lines: &[Line]) -> Labels {
+    let starts: HashSet<u16> = lines.iter().map(|line| line.address()).collect();
+    let mut jumps = HashSet::new();
+    let mut calls = HashSet::new();
+    let pchl_targets = dispatch::pchl_targets(lines);
+    for line in lines {
+        let instruction = match *line {
+            Line::Instruction(ref instruction) => instruction,
+            // Pointer tables point at things worth a label too.
+            Line::Data {
+                ref bytes,
+                format: DataFormat::Pointers,
+                ..
+            } => {
+                jumps.extend(listing::words(bytes));
+                continue;
+            }
+            _ => continue,
+        };
+        match instruction.flow() {
+            Flow::Jump(target) | Flow::ConditionalJump(target) => {
+                jumps.insert(target);
+            }
+            Flow::Call(target) | Flow::ConditionalCall(target) => {
+                calls.insert(target);
+            }
+            // LXI H,adr then PCHL is just a long way of writing JMP adr.
+            // Jump tables are labelled from their pointers.
+            Flow::Indirect => {
+                if let Some(&Target::Address(target)) = pchl_targets.get(&instruction.address) {
+                    jumps.insert(target);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut labels = Labels::new();
+    for &address in jumps.iter().chain(calls.iter()) {
+        if !starts.contains(&address) {
+            continue;
+        }
+        let prefix = if calls.contains(&address) { "SUB" } else { "L" };
+        labels.insert(address, format!("{}_{:04X}", prefix, address));
+    }
+    labels
+}
+
+// The address a name like L_18D4 or SUB_0A10 would be generated for, if it
+// looks like one.
+pub fn generated_address(name: &str) -> Option<u16> {
+    let upper = name.to_uppercase();
+    let digits = upper
+        .strip_prefix("L_")
+        .or_else(|| upper.strip_prefix("SUB_"))?;
+    if digits.len() == 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        u16::from_str_radix(digits, 16).ok()
+    } else {
+        None
+    }
+}
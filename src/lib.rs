@@ -0,0 +1,39 @@
+pub mod assembler;
+pub mod blocks;
+pub mod calls;
+pub mod cpm;
+pub mod cpu;
+pub mod decoder;
+pub mod dispatch;
+pub mod hex;
+pub mod image;
+pub mod instruction;
+pub mod invaders;
+pub mod json;
+pub mod labels;
+pub mod listing;
+pub mod map;
+pub mod number;
+pub mod platform;
+pub mod png;
+pub mod source;
+pub mod strings;
+pub mod symbols;
+pub mod syntax;
+pub mod timing;
+pub mod trace;
+pub mod xref;
+
+pub use assembler::{assemble, AssembleError, Assembly};
+pub use cpm::Cpm;
+pub use cpu::{Bus, Cpu, Flags, Ram};
+pub use decoder::{decode, DecodeError};
+pub use image::{Image, ImageError, Region};
+pub use instruction::{Flow, Instruction, Mnemonic, Operand, Register, RegisterPair};
+pub use invaders::Invaders;
+pub use labels::Labels;
+pub use listing::Line;
+pub use platform::Platform;
+pub use strings::Charset;
+pub use syntax::Syntax;
+pub use timing::Timing;
@@ -0,0 +1,285 @@
+// Turns a buffer of bytes into the lines that get printed, either by decoding
+// straight through from the start or by only decoding what a trace reached,
+// and then lays a region map over the top.
+
+use decoder::decode;
+use image::Image;
+use instruction::Instruction;
+use map::{Kind, Map};
+use std::collections::BTreeMap;
+
+// How many data bytes go on one DB line.
+const BYTES_PER_LINE: usize = 8;
+const WORDS_PER_LINE: usize = 4;
+// Text lines break after this many characters, or after a 0 byte.
+pub const TEXT_PER_LINE: usize = 32;
+
+// How a run of data gets printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    // DB
+    Bytes,
+    // DW
+    Words,
+    // DW with labels in place of the addresses
+    Pointers,
+    // DB, with the printable characters in quotes
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Instruction(Instruction),
+    // Bytes that aren't code. The warning is set when they were meant to be
+    // an instruction but there weren't enough of them. The comment is
+    // anything else worth saying about them, like what a string in a
+    // machine's own character set reads as.
+    Data {
+        address: u16,
+        bytes: Vec<u8>,
+        format: DataFormat,
+        warning: Option<String>,
+        comment: Option<String>,
+    },
+}
+
+impl Line {
+    pub fn address(&self) -> u16 {
+        match *self {
+            Line::Instruction(ref instruction) => instruction.address,
+            Line::Data { address, .. } => address,
+        }
+    }
+
+    // How many bytes of memory the line covers.
+    pub fn length(&self) -> usize {
+        match *self {
+            Line::Instruction(ref instruction) => instruction.length as usize,
+            Line::Data { ref bytes, .. } => bytes.len(),
+        }
+    }
+}
+
+// Decodes every byte in order, the way the disassembler always has. Each
+// region of the image is decoded separately.
+pub fn linear(image: &Image) -> Vec<Line> {
+    let mut lines = Vec::new();
+    for region in image.regions() {
+        let bytes = &region.bytes;
+        let mut position = 0;
+        while position < bytes.len() {
+            let address = region.start + position as u16;
+            match decode(&bytes[position..], address) {
+                Ok(instruction) => {
+                    position += instruction.length as usize;
+                    lines.push(Line::Instruction(instruction));
+                }
+                Err(error) => {
+                    // Not enough bytes for the whole instruction, so show what's
+                    // left as data rather than falling off the end of the buffer.
+                    lines.push(Line::Data {
+                        address,
+                        bytes: bytes[position..].to_vec(),
+                        format: DataFormat::Bytes,
+                        warning: Some(error.to_string()),
+                        comment: None,
+                    });
+                    position = bytes.len();
+                }
+            }
+        }
+    }
+    lines
+}
+
+// Lays out the instructions a trace found, with everything in between as data.
+pub fn traced(image: &Image, instructions: &BTreeMap<u16, Instruction>) -> Vec<Line> {
+    let mut lines = Vec::new();
+    for region in image.regions() {
+        let mut position = 0;
+        let inside = instructions
+            .range(region.start..)
+            .take_while(|&(&address, _)| region.contains(address));
+        for (&address, instruction) in inside {
+            let start = (address - region.start) as usize;
+            data_lines(
+                &region.bytes,
+                region.start,
+                position,
+                start,
+                DataFormat::Bytes,
+                &mut lines,
+            );
+            lines.push(Line::Instruction(instruction.clone()));
+            position = start + instruction.length as usize;
+        }
+        data_lines(
+            &region.bytes,
+            region.start,
+            position,
+            region.bytes.len(),
+            DataFormat::Bytes,
+            &mut lines,
+        );
+    }
+    lines
+}
+
+// Redoes the lines to match a region map. Code ranges are decoded straight
+// through, data ranges are printed the way the map says, and everywhere else
+// keeps the lines it already had. A line that would run into a mapped range
+// is cut short and shown as bytes.
+pub fn mapped(image: &Image, map: &Map, lines: &[Line]) -> Vec<Line> {
+    let starts: BTreeMap<u16, &Line> = lines.iter().map(|line| (line.address(), line)).collect();
+    let mut mapped = Vec::new();
+    for region in image.regions() {
+        let bytes = &region.bytes;
+        let mut position = 0;
+        while position < bytes.len() {
+            let address = region.start + position as u16;
+            // Where whatever is at `address` stops: the end of its range, or
+            // the start of the next one.
+            let (kind, end) = match map.range(address) {
+                Some(range) => (Some(range.kind), range.end as usize + 1),
+                None => (
+                    None,
+                    map.next_range(address)
+                        .map_or(usize::MAX, |range| range.start as usize),
+                ),
+            };
+            let end = end.min(region.end()) - region.start as usize;
+            let format = match kind {
+                Some(Kind::Code) => {
+                    match decode(&bytes[position..end], address) {
+                        Ok(instruction) => {
+                            position += instruction.length as usize;
+                            mapped.push(Line::Instruction(instruction));
+                        }
+                        Err(error) => {
+                            mapped.push(Line::Data {
+                                address,
+                                bytes: bytes[position..end].to_vec(),
+                                format: DataFormat::Bytes,
+                                warning: Some(error.to_string()),
+                                comment: None,
+                            });
+                            position = end;
+                        }
+                    }
+                    continue;
+                }
+                Some(Kind::Words) => DataFormat::Words,
+                Some(Kind::Pointers) => DataFormat::Pointers,
+                Some(Kind::Ascii) => DataFormat::Text,
+                Some(Kind::Bytes) | Some(Kind::Unknown) => DataFormat::Bytes,
+                None => match starts.get(&address) {
+                    Some(line) if position + line.length() <= end => {
+                        mapped.push((*line).clone());
+                        position += line.length();
+                        continue;
+                    }
+                    // Part of a line the map cut into, so bytes up to the
+                    // next line that's still whole.
+                    _ => {
+                        let next = starts
+                            .range(address.saturating_add(1)..)
+                            .next()
+                            .map_or(usize::MAX, |(&next, _)| next as usize);
+                        let end = end.min(next.saturating_sub(region.start as usize));
+                        data_lines(
+                            bytes,
+                            region.start,
+                            position,
+                            end,
+                            DataFormat::Bytes,
+                            &mut mapped,
+                        );
+                        position = end;
+                        continue;
+                    }
+                },
+            };
+            data_lines(bytes, region.start, position, end, format, &mut mapped);
+            position = end;
+        }
+    }
+    mapped
+}
+
+// The little endian words in a DW line.
+pub fn words(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from(pair[0]) | u16::from(*pair.get(1).unwrap_or(&0)) << 8)
+        .collect()
+}
+
+// Text as the runs of printable characters and the bytes in between, which
+// is how DB wants it: "HELLO",0DH,0AH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextPiece {
+    Text(String),
+    Byte(u8),
+}
+
+pub fn text_pieces(bytes: &[u8]) -> Vec<TextPiece> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    for &byte in bytes {
+        // A quote would end the string early, so it goes in as a number.
+        if (0x20..0x7f).contains(&byte) && byte != b'"' {
+            text.push(byte as char);
+            continue;
+        }
+        if !text.is_empty() {
+            pieces.push(TextPiece::Text(text.clone()));
+            text.clear();
+        }
+        pieces.push(TextPiece::Byte(byte));
+    }
+    if !text.is_empty() {
+        pieces.push(TextPiece::Text(text));
+    }
+    pieces
+}
+
+// Data lines for bytes[start..end], which starts at `origin` in memory.
+pub fn data_lines(
+    bytes: &[u8],
+    origin: u16,
+    start: usize,
+    end: usize,
+    format: DataFormat,
+    lines: &mut Vec<Line>,
+) {
+    let mut position = start;
+    while position < end {
+        let (format, length) = match format {
+            DataFormat::Bytes => (format, BYTES_PER_LINE),
+            // A byte left over at the end can't be a word.
+            DataFormat::Words | DataFormat::Pointers if end - position < 2 => {
+                (DataFormat::Bytes, 1)
+            }
+            DataFormat::Words | DataFormat::Pointers => {
+                (format, ((end - position) / 2 * 2).min(WORDS_PER_LINE * 2))
+            }
+            DataFormat::Text => {
+                let line = &bytes[position..end.min(position + TEXT_PER_LINE)];
+                let length = line
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .map_or(line.len(), |zero| zero + 1);
+                (format, length)
+            }
+        };
+        let line_end = end.min(position + length);
+        lines.push(Line::Data {
+            address: origin + position as u16,
+            bytes: bytes[position..line_end].to_vec(),
+            format,
+            warning: None,
+            comment: None,
+        });
+        position = line_end;
+    }
+}
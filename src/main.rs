@@ -8,1129 +8,920 @@
 // 5: Advance the pointer the number of bytes used by that instruction (1, 2, or 3 bytes)
 // 6: If not at the end of the buffer, go to step 3
 
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::listing::{self, Line};
+use eightyeighty_disassembler::map::{self, Kind, Map};
+use eightyeighty_disassembler::platform::Machine;
+use eightyeighty_disassembler::syntax::{Dialect, Hex};
+use eightyeighty_disassembler::{
+    assembler, blocks, calls, cpm, hex, invaders, json, labels, number, platform, png, source,
+    strings, symbols, trace, xref, Charset, Cpm, Flow, Image, Invaders, Labels, Platform, Syntax,
+};
+use std::collections::{BTreeMap, HashSet};
 use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
 
-fn main() {
-    let filename = env::args().nth(1).expect("Please supply a filename");
-    let mut file = File::open(&filename).unwrap();
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).unwrap();
-    let mut position = 0;
-    let end_position = buffer.len();
-    while position < end_position {
-        let hex = &format!("{:x}", buffer[position]);
-        let hex_position = &format!("{:x}", position);
-        print!("{}     ", hex_position);
-        match &hex as &str {
-            "0" => {
-                println!("00: NOP");
-                position += 1;
-            }
-            "1" => {
-                // Load byte 3 into register B. Load byte 2 into register C.
-                println!(
-                    "01: LXI B #{:x}{:x}.",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "2" => {
-                // Store A indirect
-                // The content of register A is moved to the memory location whose address is in the register pair rp.
-                // Note: only register pairs rp=B (registers B and C) or rp=D (registers D and E) may be specified.
-                println!("02: STAX B");
-                position += 1;
-            }
-            "3" => {
-                // BC <- BC + 1
-                println!("03: INX B");
-                position += 1;
-            }
-            "4" => {
-                // Increment B
-                println!("04: INR B");
-                position += 1;
-            }
-            "5" => {
-                // Decrement B
-                println!("05: DCR B");
-                position += 1;
-            }
-            "6" => {
-                // Move immediate second byte to register B
-                println!("06: MVI B #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "7" => {
-                // Rotate left.
-                // The content of the accumulator is rotated left one position.
-                // The low order bit and the CY flag are both set to the value shifted out of the high order bit position.
-                // Only the CY flag is affected.
-                println!("07: RLC");
-                position += 1;
-            }
-            "8" => {
-                println!("08: -");
-                position += 1;
-            }
-            "9" => {
-                // HL = HL + BC
-                println!("09: DAD B");
-                position += 1;
-            }
-            "a" => {
-                // Load B indirect
-                println!("0a: LDAX B");
-                position += 1;
-            }
-            "c" => {
-                // C <- C + 1;
-                println!("0c: INR C");
-                position += 1;
-            }
-            "d" => {
-                // Decrement C.
-                println!("0d: DCR C");
-                position += 1;
-            }
-            "e" => {
-                // Move byte 2 into register C.
-                // MVI stands for Move Immediate.
-                println!("0e: MVI C #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "f" => {
-                // The content of the accumulator is rotated right one position.
-                // The high order bit and the CY flag are both set to the value shifted out of the low order bit position. Only the CY flag is affected.
-                println!("0f: RRC");
-                position += 1;
-            }
-            "10" => {
-                println!("10: -");
-                position += 1;
-            }
-            "11" => {
-                // Move byte 3 into register D. Move byte 2 into register E.
-                // LXI stands for Load Immediate Register
-                println!(
-                    "11: LXI D #{:x} {:x}.",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "12" => {
-                // A <- (DE)
-                println!("12: STAX D");
-                position += 1;
-            }
-            "13" => {
-                // Increment register DE
-                println!("13: INX D");
-                position += 1;
-            }
-            "14" => {
-                // Increment Register D
-                println!("14: INR D");
-                position += 1;
-            }
-            "15" => {
-                // Decrement D
-                println!("15: DCR D");
-                position += 1;
-            }
-            "16" => {
-                // Move byte 2 into D
-                println!("16: MVI D #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "18" => {
-                println!("18: -");
-                position += 1;
-            }
-            "19" => {
-                // Add D & E to H & L
-                println!("19: DAD D");
-                position += 1;
-            }
-            "1a" => {
-                // Load A indirect
-                // The content of the memory location, whose address is in the register pair rp, is moved to register A.
-                // Note: only register pairs rp=B (registers B and C·) or rp=D (registers D and E) may be specified.
-                println!("1a: LDAX D");
-                position += 1;
-            }
-            "1b" => {
-                // DE = DE - 1
-                println!("1b: DCX D");
-                position += 1;
-            }
-            "1c" => {
-                // E <- E + 1
-                println!("1c: INR E");
-                position += 1;
-            }
-            "1d" => {
-                // E <- E - 1
-                println!("1d: DCR E");
-                position += 1;
-            }
-            "1e" => {
-                // move byte 2 into E
-                println!("1e: MVI E, {:x}", buffer[position + 1]);
-                position += 2;
-            }
-            "1f" => {
-                // 	A = A >> 1; bit 7 = prev bit 7; CY = prev bit 0
-                // Rotate A right thru carry
-                println!("1f: RAR");
-                position += 1;
-            }
-            "20" => {
-                println!("20: -");
-                position += 1;
-            }
-            "21" => {
-                // Load immediate register
-                // LXI rp, data 16
-                // Byte 3 of the instruction is moved into the high order register of the register pair rp.
-                // byte 2 of the instruction is moved into the low order register (rl) of the restier pair rp.
-                println!(
-                    "21: LXI H #{:x} {:x}.",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "22" => {
-                // Store H & L direct
-                // The content of register L is moved to the memory location whose address is specified in byte 2 and byte 3.
-                // The content of register H is moved to the next memory location.
-                // ((byte 3) (byte 2)) <- (L)
-                // ((byte 3)(byte 2) + 1) <- (H)
-                println!(
-                    "22: SHLD adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "23" => {
-                // Increment H & L registers
-                println!("23: INX H");
-                position += 1;
-            }
-            "24" => {
-                // Increment H register.
-                println!("24: INR H");
-                position += 1;
-            }
-            "25" => {
-                // H <- H - 1
-                println!("25: DCR H");
-                position += 1;
-            }
-            "26" => {
-                // Move byte into register H.
-                println!("26: MVI H #{:x}", buffer[position + 1]);
-                position += 2;
-            }
-            "27" => {
-                // Decimal Adjust A(ccumulator)
-                // The eight-bit number in the accumulator is adjusted to form two four-bit Binary-Coded-Decimal digits by the following process:
-                // If the value of the least significant 4 bits of the accumulator is greater than 9 or if the AC flag is set, 6 is added to the accumulator.
-                // If the value of the most significant 4 bits of the accumulator is now greater than 9, or if the CY flag is set, 6 is added to the most significant 4 bits of the accumulator.
-                println!("27: DAA");
-                position += 1;
-            }
-            "28" => {
-                println!("28: -");
-                position += 1;
-            }
-            "29" => {
-                // Adds HI to HL.
-                println!("29: DAD H");
-                position += 1;
-            }
-            "2a" => {
-                // Load H & L direct
-                // The content of the memory location, whose address is specified in byte 2 and byte 3 of the instruction, is moved to register L.
-                //  The content of the memory location at the succeeding address is moved to register H.
-                println!(
-                    "2a: LHLD addr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "2b" => {
-                // Decrement H & L
-                println!("2b: DCX H");
-                position += 1;
-            }
-            "2c" => {
-                // Increment Register L
-                println!("2c: INR L");
-                position += 1;
-            }
-            "2e" => {
-                // Move byte 2 into register L
-                println!("2e: MVI L #{:x}", buffer[position + 1]);
-                position += 2;
-            }
-            "2f" => {
-                // Compliment A
-                // The contents of the accumulator are complemented- (zero bits become 1, one bits become 0).
-                // A <- !A
-                println!("2f: CMA");
-                position += 1;
-            }
-            "30" => {
-                println!("30: -");
-                position += 1;
-            }
-            "31" => {
-                // move byte 3 into high order location of register SP. move byte 2 into low order location of register SP.
-                println!(
-                    "31: LXI SP,D16 #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "32" => {
-                // Store A direct.
-                // The content of the accumulator is moved to the next two bytes.
-                println!(
-                    "32: STA #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "34" => {
-                // Increment Register M (HL)
-                println!("34: INR M");
-                position += 1;
-            }
-            "35" => {
-                // Decrements Register M.
-                // The content of the memory location whose address is contained in the H and L registers is decremented by one.
-                // Sets the flags 	Z, S, P, AC
-                println!("35: DCR M");
-                position += 1;
-            }
-            "36" => {
-                // Move second byte, into register HL.
-                println!("36: MVI M #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "37" => {
-                // Set carry
-                // Sets carry flag to 1
-                println!("37: STC");
-                position += 1;
-            }
-            "38" => {
-                println!("38: -");
-                position += 1;
-            }
-            "39" => {
-                // 	HL = HL + SP
-                println!("39: DAD SP");
-                position += 1;
-            }
-            "3a" => {
-                // Load Accumulator Direct
-                // The content of the memory location, whose address is specified in byte 2 and byte 3 of the instruction, is moved to register A.
-                println!(
-                    "3a: LDA adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3
-            }
-            "3c" => {
-                // Increment Register A
-                println!("3c: INR A");
-                position += 1;
-            }
-            "3d" => {
-                // Decrement Register A
-                println!("3d: DCR A");
-                position += 1;
-            }
-            "3e" => {
-                // Move immediate register
-                println!("3e: MVI A #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "3f" => {
-                // CY=!CY
-                println!("3f: CMC");
-                position += 1;
-            }
-            "40" => {
-                println!("40: MOV B,B");
-                position += 1;
-            }
-            "41" => {
-                // Move C into B.
-                println!("41: MOV B,C");
-                position += 1;
-            }
-            "42" => {
-                // B <- D
-                println!("42: MOV B,D");
-                position += 1;
-            }
-            "43" => {
-                println!("43: MOV B,E");
-                position += 1;
-            }
-            "44" => {
-                println!("44: MOV B,H");
-                position += 1;
-            }
-            "45" => {
-                println!("45: MOV B,L");
-                position += 1;
-            }
-            "46" => {
-                // Move the contents of HL to B
-                println!("46: MOV B,M");
-                position += 1;
-            }
-            "47" => {
-                // Move contents of A to B.
-                println!("47: MOV B,A");
-                position += 1
-            }
-            "48" => {
-                // Move B into C.
-                println!("48: MOV C,B");
-                position += 1;
-            }
-            "49" => {
-                // C <- C
-                println!("49: MOV C,C");
-                position += 1;
-            }
-            "4a" => {
-                // C <- D
-                println!("4a: MOV C,D");
-                position += 1;
-            }
-            "4b" => {
-                println!("4b: MOV C,E");
-                position += 1;
-            }
-            "4c" => {
-                println!("4c: MOV C,H");
-                position += 1;
-            }
-            "4d" => {
-                println!("4d: MOV C,L");
-                position += 1;
-            }
-            "4e" => {
-                // Move HL to C
-                println!("4e: MOV C,M");
-                position += 1;
-            }
-            "4f" => {
-                // Move A to C.
-                println!("4f: MOV C,A");
-                position += 1;
-            }
-            "50" => {
-                println!("MOV D,B");
-                position += 1;
-            }
-            "51" => {
-                println!("51: MOV D,C");
-                position += 1;
-            }
-            "54" => {
-                // Move H into D
-                println!("54: MOV D,H");
-                position += 1;
-            }
-            "56" => {
-                // Move register HL into register D.
-                println!("56: MOV D,M");
-                position += 1;
-            }
-            "57" => {
-                // Move D into A.
-                println!("57: MOV D,A");
-                position += 1;
-            }
-            "59" => {
-                println!("59: MOV E,C");
-                position += 1;
-            }
-            "5b" => {
-                println!("5b: MOV E,E");
-                position += 1;
-            }
-            "5e" => {
-                // Move register HL to register E.
-                println!("5e: MOV E,M");
-                position += 1;
-            }
-            "5f" => {
-                // Move contents of A to E.
-                println!("5f: MOV E,A");
-                position += 1;
-            }
-            "60" => {
-                // Move B to H
-                println!("60: MOV H,B");
-                position += 1;
-            }
-            "61" => {
-                // Move contents of register C to register H
-                println!("61: MOV H,C");
-                position += 1;
-            }
-            "62" => {
-                println!("62: MOV H,D");
-                position += 1;
-            }
-            "63" => {
-                println!("63: MOV H,E");
-                position += 1;
-            }
-            "64" => {
-                // Move H to H (??)
-                println!("64: MOV H,H");
-                position += 1;
-            }
-            "65" => {
-                // H <- L
-                println!("65: MOV H,L");
-                position += 1;
-            }
-            "66" => {
-                // Move memory (HL) to register H
-                // H <- (HL)
-                println!("66: MOV H,M");
-                position += 1;
-            }
-            "67" => {
-                // Move register A to register H
-                println!("67: MOV H,A");
-                position += 1;
-            }
-            "68" => {
-                // Move register B to register A.
-                println!("68: MOV L,B");
-                position += 1;
-            }
-            "69" => {
-                // Move value of C into register L.
-                println!("69: MOV L,C");
-                position += 1;
-            }
-            "6c" => {
-                // L <- H
-                println!("6c: MOV L,H");
-                position += 1;
-            }
-            "6d" => {
-                println!("6d: MOV L,L");
-                position += 1;
-            }
-            "6e" => {
-                println!("6e: MOV L,M");
-                position += 1;
-            }
-            "6f" => {
-                // Move memory (L) to register A
-                // L <- A
-                println!("6f: MOV L,A");
-                position += 1;
-            }
-            "70" => {
-                // Move register B to register M (HL);
-                println!("70: MOV M,B");
-                position += 1;
-            }
-            "71" => {
-                // Move register C into register M.
-                println!("71: MOV M,C");
-                position += 1;
-            }
-            "72" => {
-                // Move register D into register M(HL);
-                println!("72: MOV M,D");
-                position += 1;
-            }
-            "73" => {
-                // Move register E to register M (HL)
-                println!("73: MOV M,E");
-                position += 1;
-            }
-            "74" => {
-                // (HL) <- H
-                println!("74: MOV M,H");
-                position += 1;
-            }
-            "76" => {
-                // HALT (??)
-                // The processor is stopped.
-                println!("76: HLT");
-                position += 1;
-            }
-            "77" => {
-                // Move register A to register HL.
-                println!("77: MOV M,A");
-                position += 1;
-            }
-            "78" => {
-                // Move contents of register B to register A.
-                println!("78: MOV A,B");
-                position += 1;
-            }
-            "79" => {
-                // Move register C to A.
-                println!("79: MOV A,C");
-                position += 1;
-            }
-            "7a" => {
-                // Move contents of register D to register A
-                println!("7a: MOV A,D");
-                position += 1;
-            }
-            "7b" => {
-                // Move contents of E to A
-                println!("7b: MOV A,E");
-                position += 1;
-            }
-            "7c" => {
-                // Move register H into register A.
-                println!("7c: MOV A,H");
-                position += 1;
-            }
-            "7d" => {
-                // Move contents of L to A
-                println!("7d: MOV A,L");
-                position += 1;
-            }
-            "7e" => {
-                // Move memory to register
-                println!("7e: MOV A,M");
-                position += 1;
-            }
-            "7f" => {
-                // A <- A
-                println!("7f: MOV A,A");
-                position += 1;
-            }
-            "80" => {
-                // Add value of B to the accumulator to A
-                println!("80: ADD B");
-                position += 1;
-            }
-            "81" => {
-                // A <- A + C
-                println!("81: ADD C");
-                position += 1;
-            }
-            "82" => {
-                // A <- A + D
-                println!("82: ADD D");
-                position += 1;
-            }
-            "83" => {
-                // Add E to A.
-                println!("83: ADD E");
-                position += 1;
-            }
-            "84" => {
-                // A <- A + H;
-                println!("84: ADD H");
-                position += 1;
-            }
-            "85" => {
-                // Adds register L to register A.
-                println!("85: ADD L");
-                position += 1;
-            }
-            "86" => {
-                // Add M (HL) to register A.
-                println!("86: ADD M");
-                position += 1;
-            }
-            "88" => {
-                // A <- A + B + CY
-                println!("88: ADC B");
-                position += 1;
-            }
-            "8a" => {
-                // Add D, and CY to A.
-                println!("8a: ADC D");
-                position += 1;
-            }
-            "8b" => {
-                // A <- A + E + CY
-                println!("8b: ADC E");
-                position += 1;
-            }
-            "8e" => {
-                // 	A <- A + (HL) + CY
-                println!("8e: ADC M");
-                position += 1;
-            }
-            "90" => {
-                // A <- A + B
-                println!("90: SUB B");
-                position += 1;
-            }
-            "94" => {
-                // A <- A + H
-                println!("94: SUB H");
-                position += 1;
-            }
-            "97" => {
-                // Subtract value of A from A (i guess sets A to 0)
-                println!("97: SUB A");
-                position += 1;
-            }
-            "98" => {
-                // 	A <- A - B - CY
-                println!("98: SBB B");
-                position += 1;
-            }
-            "99" => {
-                // A <- A - C - CY
-                println!("99: SBB C");
-                position += 1;
-            }
-            "9a" => {
-                // A <- A - D - CY
-                println!("9a: SBB D");
-                position += 1;
-            }
-            "9b" => {
-                // 	A <- A - E - CY
-                println!("9b: SBB E");
-                position += 1;
-            }
-            "9d" => {
-                // A <- A - L - CY
-                println!("9d: SBB L");
-                position += 1;
-            }
-            "9e" => {
-                // 	A <- A - (HL) - CY
-                println!("9e: SBB M");
-                position += 1;
-            }
-            "a0" => {
-                // Does an and of A & B on A
-                println!("a0: ANA B");
-                position += 1;
-            }
-            "a3" => {
-                // A <- A * E
-                println!("a3: ANA E");
-                position += 1;
-            }
-            "a6" => {
-                // 	A <- A & (HL)
-                println!("a6: ANA M");
-                position += 1;
-            }
-            "a7" => {
-                // AND register
-                // The content of register r is logically anded with the content of the accumulator.
-                // The result is placed in the accumulator. The CY flag is cleared.
-                println!("a7: ANA A");
-                position += 1;
-            }
-            "a8" => {
-                // 	A <- A ^ B
-                // Exclusive OR
-                println!("a8: XRA B");
-                position += 1;
-            }
-            "aa" => {
-                // A <- A ^ D
-                println!("aa: XRA D");
-                position += 1;
-            }
-            "af" => {
-                // Exclusive OR Register
-                // The content of register r is exclusive-or'd with the content of the accumulator. The result is placed in the accumulator. The CY and AC flags are cleared.
-                println!("af: XRA A");
-                position += 1;
-            }
-            "b" => {
-                // Decrement Register BC
-                println!("0b: DCX B");
-                position += 1;
-            }
-            "b0" => {
-                // A <- A | B
-                // Or register with A.
-                // Does an OR with register B on register A.
-                println!("b0: ORA B");
-                position += 1;
-            }
-            "b3" => {
-                // A <- A | E
-                println!("b3: ORA E");
-                position += 1;
-            }
-            "b4" => {
-                // A <- A | H
-                // Or register H with register A.
-                println!("b4: ORA H");
-                position += 1;
-            }
-            "b6" => {
-                // Does an or with whats in register M (HL) versus accumulator
-                println!("b6: ORA M");
-                position += 1;
-            }
-            "b8" => {
-                // Compare register B to A.
-                // Contents of register B are substracted from A.
-                // couldnt tell you why its called compare
-                println!("b8: CMP B");
-                position += 1;
-            }
-            "bb" => {
-                // A - E
-                println!("bb: CMP E");
-                position += 1;
-            }
-            "bc" => {
-                // A - H
-                println!("bc: CMP H");
-                position += 1;
-            }
-            "be" => {
-                // A - (HL)
-                // Subtract HL from A.
-                println!("be: CMP M");
-                position += 1;
-            }
-            "c0" => {
-                // If NZ (non-zero), RET (return).
-                println!("c0: RNZ");
-                position += 1;
-            }
-            "c1" => {
-                // Pop register pair B & C off stack
-                println!("c1: POP B");
-                position += 1;
-            }
-            "c2" => {
-                // Jump on no zero.
-                // not sure, but i think its jump if a check is non zero.
-                println!(
-                    "c2: JNZ adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "c3" => {
-                // JMP, uses next two bytes to determine where to jump to.
-                println!(
-                    "c3: JMP #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "c4" => {
-                // Call address if non zero
-                // Not sure what it checks? whats non zero
-                println!(
-                    "c4: CNZ adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "c5" => {
-                // Push register Pair B & C on stack
-                println!("c5: PUSH B");
-                position += 1;
-            }
-            "c6" => {
-                // Add immediate to A.
-                // The content of the second byte of the instruction is added to the content of the accumulator.
-                // The result is placed in the accumulator.
-                println!("c6: ADI #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "c8" => {
-                // 	If Z, RET
-                // if what is zero??
-                println!("c8: RZ");
-                position += 1;
-            }
-            "c9" => {
-                // Return
-                // The content of the memory location whose address is specified in register SP is moved to the low-order eight bits of register PC.
-                // The content of the memory location whose address is one more than the content of register SP is moved to the high-order eight bits of register PC. The content of register SP is incremented by 2.
-                println!("c9: RET");
-                position += 1;
-            }
-            "ca" => {
-                // Jump on zero
-                // not sure. i imagine it checks to see if something is 0, then jumps...
-                println!(
-                    "ca: JZ adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "cc" => {
-                // If Z, call adr
-                // Z is one of the flags. Must check if that flag is set then calls.
-                println!(
-                    "cc: CZ adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "cd" => {
-                // The high-order eight bits of the next instruction address are moved to the memory location whose address is one less than the content of register SP.
-                // The low-order eight bits of the next instruction address are moved to the memory location whose address is two less than the content of register SP.
-                // The content of register SP is decremented by 2. Control is transferred to the instruction whose address is specified in byte 3 and byte 2 of the current instruction.
-                println!(
-                    "cd: CALL adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "d0" => {
-                // 	if NCY, RET
-                // return on no carry. if the carry flag hasn't been set, return.
-                println!("d0: RNC");
-                position += 1;
-            }
-            "d1" => {
-                // Pop register pair D & E off stack
-                println!("d1: POP D");
-                position += 1;
-            }
-            "d2" => {
-                // Jump if no carry
-                // not sure, but i imagine it's jump if the no carry flag hasn't been set.
-                println!(
-                    "d2: JNC #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3
-            }
-            "d3" => {
-                // Output
-                // The content of register A is placed on the eight bit bi-directional data bus for transmission to the specified port.
-                println!("d3: OUT #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "d4" => {
-                // If NCY (no carry) call address.
-                println!(
-                    "d4: CNC adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "d5" => {
-                // Push register Pair D & E on stack
-                println!("d5: PUSH D");
-                position += 1;
-            }
-            "d6" => {
-                // Subtract immediate from A
-                // Subtracts the byte value from A.
-                println!("d6: SUI #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "d8" => {
-                // if carry flag is set, return.
-                println!("d8: RC");
-                position += 1;
-            }
-            "da" => {
-                // Conditional Jump
-                // If the specified condition is true, control is transferred to the instruction whose address is specified in byte 3 and byte 2 of the current instruction; other- wise, control continues sequentially.
-                println!(
-                    "da: JC adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
+const USAGE: &str = "Usage: eightyeighty-disassembler [--origin ADDRESS] [--offsets] [--recursive]
+                                 [--entry ADDRESS]... [--no-labels] [--cycles]
+                                 [--cycle-range START-END]
+                                 [--format listing|asm|dot|tree|callgraph|json|jsonl] [--routine ADDRESS]... [--verify] [--platform NAME|FILE]
+                                 [--symbols FILE]... [--map FILE]
+                                 [--strings] [--min-string N] [--charset FILE] [--xref]
+                                 [--hex '#'|0x|h|$] [--pad] [--lowercase] [--zilog]
+                                 FILE[@ADDRESS]...
+       eightyeighty-disassembler --assemble --output BINARY SOURCE
+       eightyeighty-disassembler --run [--args TEXT] [--directory DIR] [--trace] PROGRAM.COM
+       eightyeighty-disassembler --run --platform invaders [--frames N] [--snapshot-every N]
+                                 [--directory DIR] ROM[@ADDRESS]...
+
+Each FILE can be a raw binary or an Intel HEX file. Several binaries (like the
+ROM chips off one board) are loaded into the same memory, each at its ADDRESS,
+or straight after the one before if it doesn't have one.
+
+Options:
+    --origin ADDRESS   Address the first file gets loaded at if it doesn't have an @ADDRESS,
+                       e.g. 0x0100 for a CP/M .COM file (default 0)
+    --offsets          Show the file offset of each line as well as its address
+    --recursive        Only decode code reachable from the entry points, the rest is shown as data
+    --entry ADDRESS    Start tracing from ADDRESS, can be given more than once (implies --recursive)
+                       Defaults to the origin and the RST vectors
+    --no-labels        Print jump and call destinations as plain addresses
+    --cycles           Show the T-states each instruction takes, with a running total for each block
+    --cycle-range START-END
+                       Add up the T-states of the instructions from START to END
+    --format FORMAT    listing (the default), asm for source that assembles back to the same bytes,
+                       dot for a Graphviz graph of the basic blocks in each routine,
+                       tree for what calls what, with recursion, unreachable routines and
+                       ones only reached through PCHL, callgraph for that as a Graphviz graph,
+                       json for every line as one JSON document, or jsonl for JSON Lines
+    --routine ADDRESS  Only graph the routine starting at ADDRESS, can be given more than once
+    --verify           Check the asm output assembles back to exactly the input file
+    --assemble         Assemble SOURCE instead of disassembling, writing the bytes to --output
+    --output FILE      Where --assemble writes the binary
+    --platform NAME|FILE
+                       The machine the code runs on, which names its I/O ports and memory
+                       and sets the origin: invaders, altair, imsai, cpm or a platform file
+    --symbols FILE     Names for addresses, used instead of generated labels and bare
+                       addresses. NAME = 0x1234 lines, or a zmac, asl or .SYM symbol table
+    --map FILE         Region map saying which address ranges are code, bytes, words,
+                       pointers, ascii or unknown, e.g. 0x1a00-0x1bff bytes sprites
+    --strings          Show runs of characters in the data as strings, e.g. DB \"INSERT COIN\"
+    --min-string N     Shortest run that counts as a string (default 4)
+    --charset FILE     Character codes to use for --strings in place of ASCII, from the
+                       [charset] section of a platform file (default the platform's, if any)
+    --xref             Say what calls and jumps to each label, and list every address the
+                       code reads, writes, jumps to or calls with where from, at the end
+    --hex STYLE        How hex numbers are written: # for #1a2b (the listing's default),
+                       0x for 0x1a2b, h for 1A2BH (the asm default) or $ for $1a2b
+    --pad              Always give bytes two hex digits and words four
+    --lowercase        Write mnemonics, registers and directives in lower case, and 1a2bh
+    --zilog            Use Zilog's Z80 mnemonics (LD A,(HL), JP NZ,...) and DEFB, DEFW
+                       and DEFM. --verify still checks the Intel version
+    --cpm              Same as --platform cpm, which also names the BDOS functions called
+    --run              Run a CP/M .COM program, with the console on stdin and stdout, or
+                       with --platform invaders, run the game
+    --args TEXT        The command line to give the program, e.g. \"B:FOO.TXT /X\"
+    --directory DIR    Host directory the program's files are in, or where screenshots go
+                       (default .)
+    --trace            Print each instruction to stderr as --run runs it
+    --frames N         How many 60ths of a second to run Space Invaders for (default 600)
+    --snapshot-every N Save the screen as a PNG every N frames (default just the last one)";
+
+#[derive(PartialEq)]
+enum Format {
+    Listing,
+    Asm,
+    Dot,
+    Tree,
+    CallGraph,
+    Json,
+    JsonLines,
+}
+
+struct Options {
+    filenames: Vec<String>,
+    origin: u16,
+    offsets: bool,
+    recursive: bool,
+    entry_points: Vec<u16>,
+    labels: bool,
+    cycles: bool,
+    cycle_range: Option<(u16, u16)>,
+    format: Format,
+    routines: Vec<u16>,
+    verify: bool,
+    assemble: bool,
+    output: Option<String>,
+    platform: Option<Platform>,
+    symbols: Vec<String>,
+    map: Option<String>,
+    strings: bool,
+    min_string: usize,
+    charset: Option<Charset>,
+    xref: bool,
+    hex: Option<Hex>,
+    pad: bool,
+    lowercase: bool,
+    zilog: bool,
+    run: bool,
+    arguments: String,
+    directory: String,
+    trace: bool,
+    frames: u64,
+    snapshot_every: Option<u64>,
+}
+
+impl Options {
+    // How to write the code out, starting from the way the format usually
+    // does it.
+    fn syntax(&self, base: Syntax) -> Syntax {
+        Syntax {
+            hex: self.hex.unwrap_or(base.hex),
+            pad: base.pad || self.pad,
+            uppercase: !self.lowercase,
+            dialect: if self.zilog {
+                Dialect::Zilog
+            } else {
+                Dialect::Intel
+            },
+        }
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
+
+// For a file that can't be read or written, like one that isn't there.
+fn file_error<E: Display>(filename: &Path, error: E) -> ! {
+    eprintln!("{}: {}", filename.display(), error);
+    process::exit(1);
+}
+
+// For output that couldn't be written. A reader that's gone away, like
+// `| head`, has all it wanted, so that's not an error.
+fn output_error(error: io::Error) -> ! {
+    if error.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    eprintln!("Couldn't write the output: {}", error);
+    process::exit(1);
+}
+
+// A built in platform by name, or else a platform file.
+fn load_platform(name: &str) -> Platform {
+    if let Some(platform) = Platform::builtin(name) {
+        return platform;
+    }
+    let text = fs::read_to_string(name).unwrap_or_else(|_| {
+        usage_error(&format!(
+            "{} isn't a platform file or one of: {}",
+            name,
+            Platform::builtin_names().join(", ")
+        ))
+    });
+    platform::parse(&text).unwrap_or_else(|error| {
+        eprintln!("{}: {}", name, error);
+        process::exit(1);
+    })
+}
+
+fn parse_options() -> Options {
+    let mut filenames = Vec::new();
+    let mut origin = None;
+    let mut offsets = false;
+    let mut recursive = false;
+    let mut entry_points = Vec::new();
+    let mut labels = true;
+    let mut cycles = false;
+    let mut cycle_range = None;
+    let mut format = Format::Listing;
+    let mut routines = Vec::new();
+    let mut verify = false;
+    let mut assemble = false;
+    let mut output = None;
+    let mut platform = None;
+    let mut symbols = Vec::new();
+    let mut map = None;
+    let mut strings = false;
+    let mut min_string = strings::MIN_LENGTH;
+    let mut charset = None;
+    let mut xref = false;
+    let mut hex = None;
+    let mut pad = false;
+    let mut lowercase = false;
+    let mut zilog = false;
+    let mut run = false;
+    let mut arguments = String::new();
+    let mut directory = ".".to_string();
+    let mut trace = false;
+    let mut frames = 600;
+    let mut snapshot_every = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg as &str {
+            "--origin" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--origin needs an address"));
+                origin = Some(
+                    number::address(&value)
+                        .unwrap_or_else(|| usage_error(&format!("Bad origin: {}", value))),
                 );
-                position += 3;
-            }
-            "db" => {
-                // IN port
-                // The data placed on the eight bit bi-directional data bus by the specified port is moved to register A.
-                println!("db: IN #{:x}.", buffer[position + 1]);
-                position += 2;
             }
-            "de" => {
-                // Subtract immediate from A with borrow
-                // The contents of the second byte of the instruction and the contents of the CY flag are both subtracted from the accumulator.
-                // The result is placed in the accumulator.
-                println!("de: SPI #{:x}.", buffer[position + 1]);
-                position += 1;
-            }
-            "e0" => {
-                // 	if PO, RET
-                println!("eo: RPO");
-                position += 1;
-            }
-            "e1" => {
-                // Pop register pair H & L off stack
-                println!("e1: POP H");
-                position += 1;
-            }
-            "e2" => {
-                // Jump on parity odd
-                println!(
-                    "e2: JPO adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
+            "--offsets" => offsets = true,
+            "--recursive" => recursive = true,
+            "--entry" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--entry needs an address"));
+                let address = number::address(&value)
+                    .unwrap_or_else(|| usage_error(&format!("Bad entry point: {}", value)));
+                entry_points.push(address);
+                recursive = true;
+            }
+            "--no-labels" => labels = false,
+            "--cycles" => cycles = true,
+            "--cycle-range" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--cycle-range needs START-END"));
+                let range = value.find('-').and_then(|dash| {
+                    Some((
+                        number::address(&value[..dash])?,
+                        number::address(&value[dash + 1..])?,
+                    ))
+                });
+                let (start, end) =
+                    range.unwrap_or_else(|| usage_error(&format!("Bad range: {}", value)));
+                if start > end {
+                    usage_error(&format!("{} ends before it starts", value));
+                }
+                cycle_range = Some((start, end));
+            }
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("listing") => Format::Listing,
+                    Some("asm") => Format::Asm,
+                    Some("dot") => Format::Dot,
+                    Some("tree") => Format::Tree,
+                    Some("callgraph") => Format::CallGraph,
+                    Some("json") => Format::Json,
+                    Some("jsonl") => Format::JsonLines,
+                    _ => usage_error(
+                        "--format needs to be listing, asm, dot, tree, callgraph, json or jsonl",
+                    ),
+                }
+            }
+            "--routine" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--routine needs an address"));
+                routines.push(
+                    number::address(&value)
+                        .unwrap_or_else(|| usage_error(&format!("Bad routine: {}", value))),
                 );
-                position += 3;
-            }
-            "e3" => {
-                // Exchange top of stack, H & L.
-                // The content of the L register is exchanged with the content of the memory location whose address is specified by the content of register SP.
-                // The content of the H register is exchanged with the content of the memory location whose address is one more than the content of register SP.
-                println!("e3: XTHL");
-                position += 1;
-            }
-            "e5" => {
-                // Push register Pair H & L on stack
-                println!("e5: PUSH H");
-                position += 1;
             }
-            "e6" => {
-                // And immediate with A.
-                // The content of the second byte of the instruction is logically anded with the contents of the accumulator.
-                // The result is placed in the accumulator. The CY and AC flags are cleared.
-                println!("e6: ANI #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "e9" => {
-                // H & L to program counter.
-                // The content of register H is moved to the high-order eight bits of register PC.
-                // The content of register l is moved to the low-order eight bits of register PC.
-                println!("e9: PCHL");
-                position += 1;
-            }
-            "eb" => {
-                // Exchange D & E, H& L Registers
-                println!("eb: XCHG");
-                position += 1;
-            }
-            "ec" => {
-                println!(
-                    "ec: CPE adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 3;
-            }
-            "ee" => {
-                // Exclusive Or immediate with A
-                println!("ee: XRI #{:x}", buffer[position + 1]);
-                position += 2;
-            }
-            "f0" => {
-                // if P, RET
-                println!("f0: RP");
-                position += 1;
-            }
-            "f1" => {
-                // Pop A and Flags off stack
-                println!("f1: POP PSW");
-                position += 1;
-            }
-            "f5" => {
-                // PSW means "processor state word", adds 1 to accumulator.
-                // Push A and Flags on stack
-                println!("f5: PUSH PSW");
-                position += 1;
-            }
-            "f6" => {
-                // does an or of A and the byte data, loads that into the accumulator.
-                println!("f6: ORI #{:x}.", buffer[position + 1]);
-                position += 2;
-            }
-            "f8" => {
-                // if M, return
-                println!("f8: RM");
-                position += 1;
+            "--verify" => verify = true,
+            "--assemble" => assemble = true,
+            "--output" => {
+                output = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage_error("--output needs a filename")),
+                )
+            }
+            "--platform" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--platform needs a name or file"));
+                platform = Some(load_platform(&value));
+            }
+            "--symbols" => symbols.push(
+                args.next()
+                    .unwrap_or_else(|| usage_error("--symbols needs a filename")),
+            ),
+            "--map" => {
+                map = Some(
+                    args.next()
+                        .unwrap_or_else(|| usage_error("--map needs a filename")),
+                )
+            }
+            "--strings" => strings = true,
+            "--min-string" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--min-string needs a number"));
+                min_string = match value.parse() {
+                    Ok(0) | Err(_) => usage_error(&format!("Bad string length: {}", value)),
+                    Ok(length) => length,
+                };
+            }
+            "--charset" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--charset needs a filename"));
+                charset = Some(load_platform(&value).charset.unwrap_or_else(|| {
+                    usage_error(&format!("{} doesn't have a [charset] section", value))
+                }));
+            }
+            "--xref" => xref = true,
+            "--hex" => {
+                hex = match args.next().as_deref() {
+                    Some("#") => Some(Hex::Hash),
+                    Some("0x") => Some(Hex::C),
+                    Some("h") => Some(Hex::Intel),
+                    Some("$") => Some(Hex::Dollar),
+                    _ => usage_error("--hex needs to be #, 0x, h or $"),
+                }
+            }
+            "--pad" => pad = true,
+            "--lowercase" => lowercase = true,
+            "--zilog" => zilog = true,
+            "--cpm" => platform = Platform::builtin("cpm"),
+            "--run" => run = true,
+            "--args" => {
+                arguments = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--args needs the command line"))
+            }
+            "--directory" => {
+                directory = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--directory needs a directory"))
+            }
+            "--trace" => trace = true,
+            "--frames" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--frames needs a number"));
+                frames = value
+                    .parse()
+                    .unwrap_or_else(|_| usage_error(&format!("Bad frame count: {}", value)));
+            }
+            "--snapshot-every" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| usage_error("--snapshot-every needs a number"));
+                snapshot_every = match value.parse() {
+                    Ok(0) | Err(_) => usage_error(&format!("Bad frame count: {}", value)),
+                    Ok(every) => Some(every),
+                };
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with("--") => usage_error(&format!("Unknown option: {}", arg)),
+            _ => filenames.push(arg),
+        }
+    }
+    let default_origin = platform.as_ref().map_or(0, |platform| platform.origin);
+    Options {
+        filenames,
+        origin: origin.unwrap_or(default_origin),
+        offsets,
+        recursive,
+        entry_points,
+        labels,
+        cycles,
+        cycle_range,
+        format,
+        routines,
+        verify,
+        assemble,
+        output,
+        platform,
+        symbols,
+        map,
+        strings,
+        min_string,
+        charset,
+        xref,
+        hex,
+        pad,
+        lowercase,
+        zilog,
+        run,
+        arguments,
+        directory,
+        trace,
+        frames,
+        snapshot_every,
+    }
+}
+
+fn main() {
+    let options = parse_options();
+    if options.assemble {
+        assemble(&options);
+    } else if options.run
+        && options
+            .platform
+            .as_ref()
+            .is_some_and(|platform| platform.machine == Some(Machine::Invaders))
+    {
+        run_invaders(&options);
+    } else if options.run {
+        run(&options);
+    } else {
+        disassemble(&options);
+    }
+}
+
+fn assemble(options: &Options) {
+    let output = options
+        .output
+        .as_ref()
+        .unwrap_or_else(|| usage_error("--assemble needs --output"));
+    let filename = match options.filenames[..] {
+        [ref filename] => filename,
+        _ => usage_error("--assemble takes one source file"),
+    };
+    let text =
+        fs::read_to_string(filename).unwrap_or_else(|error| file_error(filename.as_ref(), error));
+    match assembler::assemble(&text) {
+        Ok(assembly) => fs::write(output, &assembly.bytes)
+            .unwrap_or_else(|error| file_error(output.as_ref(), error)),
+        Err(error) => {
+            eprintln!("{}: {}", filename, error);
+            process::exit(1);
+        }
+    }
+}
+
+// Runs a .COM file under CP/M until it warm boots.
+fn run(options: &Options) {
+    let filename = match options.filenames[..] {
+        [ref filename] => filename,
+        _ => usage_error("--run takes one .COM file"),
+    };
+    let program = fs::read(filename).unwrap_or_else(|error| file_error(filename.as_ref(), error));
+    let mut machine = Cpm::new(
+        &program,
+        &options.arguments,
+        PathBuf::from(&options.directory),
+    );
+    while !machine.finished() {
+        if options.trace {
+            let pc = machine.cpu.pc;
+            if pc == cpm::BDOS {
+                let function = machine.cpu.c;
+                match cpm::bdos_function_name(function) {
+                    Some(name) => eprintln!("{:x}     BDOS {}: {}", pc, function, name),
+                    None => eprintln!("{:x}     BDOS {}", pc, function),
+                }
+            } else {
+                eprintln!("{:x}     {}", pc, machine.cpu.fetch(&mut machine.ram));
             }
-            "fa" => {
-                //	if M, PC <- adr
-                // Jump on Minus
-                // ok, cool. you can see this in 4-11
-                // minus is if S = 1
-                // S is a flag that stands for Sign
-                println!(
-                    "fa: JM adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
+        }
+        machine.step();
+    }
+}
+
+// Runs Space Invaders for a while, saving the screen as it goes.
+fn run_invaders(options: &Options) {
+    let (image, _, _) = load(options);
+    let mut machine = Invaders::new(&image);
+    let every = options.snapshot_every.unwrap_or(options.frames);
+    while machine.frames < options.frames {
+        machine.run_frame();
+        if machine.frames % every == 0 {
+            let path =
+                PathBuf::from(&options.directory).join(format!("frame-{:05}.png", machine.frames));
+            let mut file = File::create(&path).unwrap_or_else(|error| file_error(&path, error));
+            png::write_greyscale(
+                &mut file,
+                invaders::WIDTH as u32,
+                invaders::HEIGHT as u32,
+                &machine.screen(),
+            )
+            .unwrap_or_else(|error| file_error(&path, error));
+            eprintln!("Saved {}", path.display());
+        }
+    }
+}
+
+// Assembles the asm output again and checks it matches what we started with.
+fn verify(image: &Image, lines: &[Line], labels: &Labels) {
+    let mut text = Vec::new();
+    source::write(&mut text, lines, labels).unwrap();
+    let assembly = match assembler::assemble(&String::from_utf8_lossy(&text)) {
+        Ok(assembly) => assembly,
+        Err(error) => {
+            eprintln!("Round trip failed: {}", error);
+            process::exit(1);
+        }
+    };
+    for region in image.regions() {
+        for (address, &original) in (region.start as usize..).zip(&region.bytes) {
+            let assembled = address
+                .checked_sub(assembly.origin as usize)
+                .and_then(|position| assembly.bytes.get(position));
+            if assembled != Some(&original) {
+                eprintln!(
+                    "Round trip failed: output differs from input at {:x}",
+                    address
                 );
-                position += 3;
-            }
-            "fb" => {
-                // Enable interrupts
-                // The interrupt system is enabled following the execu- tion of the next instruction.
-                println!("fb: EI");
-                position += 1;
+                process::exit(1);
             }
-            "fc" => {
-                // if M, CALL adr
-                println!(
-                    "fc: CM adr #{:x} {:x}",
-                    buffer[position + 2],
-                    buffer[position + 1]
-                );
-                position += 1;
+        }
+    }
+    eprintln!("Round trip OK");
+}
+
+// A binary file and where it went, so lines can be traced back to a file offset.
+struct Loaded {
+    filename: String,
+    start: u16,
+    length: usize,
+}
+
+// Loads every file into one memory image. Files that look like Intel HEX go
+// wherever their records say, anything else is a raw binary loaded at its
+// @ADDRESS, or straight after the previous binary (the first one goes at the
+// origin). Also hands back the start address if a HEX file had one.
+fn load(options: &Options) -> (Image, Option<u16>, Vec<Loaded>) {
+    if options.filenames.is_empty() {
+        usage_error("Please supply a filename");
+    }
+    let mut image = Image::new();
+    let mut start = None;
+    let mut loaded: Vec<Loaded> = Vec::new();
+    for argument in &options.filenames {
+        let (filename, address) = match argument.rfind('@') {
+            Some(at) => {
+                let address = number::address(&argument[at + 1..])
+                    .unwrap_or_else(|| usage_error(&format!("Bad load address: {}", argument)));
+                (&argument[..at], Some(address))
+            }
+            None => (&argument[..], None),
+        };
+        let buffer =
+            fs::read(filename).unwrap_or_else(|error| file_error(filename.as_ref(), error));
+        let result = if hex::is_hex(&buffer) {
+            if address.is_some() {
+                usage_error(&format!(
+                    "{} is Intel HEX so already says where it goes",
+                    filename
+                ));
+            }
+            match hex::parse(&String::from_utf8_lossy(&buffer)) {
+                Ok(file) => {
+                    start = start.or(file.start);
+                    file.image
+                        .regions()
+                        .iter()
+                        .try_for_each(|region| image.add(region.start, &region.bytes))
+                }
+                Err(error) => {
+                    eprintln!("{}: {}", filename, error);
+                    process::exit(1);
+                }
+            }
+        } else {
+            let address = address.unwrap_or_else(|| match loaded.last() {
+                Some(previous) => (previous.start as usize + previous.length) as u16,
+                None => options.origin,
+            });
+            loaded.push(Loaded {
+                filename: filename.to_string(),
+                start: address,
+                length: buffer.len(),
+            });
+            image.add(address, &buffer)
+        };
+        if let Err(error) = result {
+            eprintln!("Can't load {}: {}", filename, error);
+            process::exit(1);
+        }
+    }
+    // Gaps are normal for a ROM set with a chip missing, but worth knowing about.
+    for pair in image.regions().windows(2) {
+        eprintln!(
+            "note: nothing loaded from {:04x} to {:04x}",
+            pair[0].end(),
+            pair[1].start - 1
+        );
+    }
+    (image, start, loaded)
+}
+
+// Where a line came from, as an offset into its file. With more than one
+// file the file's name goes in front.
+fn offset(loaded: &[Loaded], address: u16) -> String {
+    let file = loaded.iter().find(|file| {
+        address >= file.start && (address as usize) < file.start as usize + file.length
+    });
+    match file {
+        Some(file) if loaded.len() > 1 => {
+            format!("{}:{:x}", file.filename, address - file.start)
+        }
+        Some(file) => format!("{:x}", address - file.start),
+        None => "-".to_string(),
+    }
+}
+
+// Adds up the T-states of every instruction between two addresses, taking
+// all the conditional calls and returns one way and then the other.
+fn print_cycle_range(lines: &[Line], start: u16, end: u16) {
+    let mut least = 0u32;
+    let mut most = 0u32;
+    for line in lines {
+        if let Line::Instruction(ref instruction) = *line {
+            if instruction.address >= start && instruction.address <= end {
+                let timing = instruction.timing();
+                least += u32::from(timing.cycles);
+                most += u32::from(timing.most());
             }
-            "fe" => {
-                // Compare immediate
-                // The content of the second byte of the instruction is subtracted from the accumulator.
-                // The condition flags are set by the result of the subtraction.
-                // The Z flag is set to 1 if (A) = (byte 2). The CY flag is set to 1 if (A) <(byte 2).
-                println!("fe: CPI #{:x}.", buffer[position + 1],);
-                position += 2;
+        }
+    }
+    if least == most {
+        println!("{:x}-{:x} takes {} T-states", start, end, least);
+    } else {
+        println!("{:x}-{:x} takes {} to {} T-states", start, end, least, most);
+    }
+}
+
+fn print_references(references: &BTreeMap<u16, Vec<xref::Reference>>, labels: &Labels) {
+    println!();
+    println!("Cross references:");
+    for (&address, list) in references {
+        println!(
+            "{:04x} {:<12} {}",
+            address,
+            labels.get(address).unwrap_or(""),
+            xref::describe(list)
+        );
+    }
+}
+
+fn load_map(filename: &str) -> Map {
+    let text =
+        fs::read_to_string(filename).unwrap_or_else(|error| file_error(filename.as_ref(), error));
+    map::parse(&text).unwrap_or_else(|error| {
+        eprintln!("{}: {}", filename, error);
+        process::exit(1);
+    })
+}
+
+fn disassemble(options: &Options) {
+    let (image, start, loaded) = load(options);
+    let mut map = options
+        .map
+        .as_ref()
+        .map_or_else(Map::new, |filename| load_map(filename));
+    let lines = if options.recursive {
+        let mut entry_points = if !options.entry_points.is_empty() {
+            options.entry_points.clone()
+        } else {
+            let mut entry_points = trace::default_entry_points(&image);
+            entry_points.extend(start);
+            entry_points
+        };
+        // Whatever the map says is code is somewhere to start from too.
+        entry_points.extend(
+            map.ranges()
+                .iter()
+                .filter(|range| range.kind == Kind::Code)
+                .map(|range| range.start),
+        );
+        let traced = trace::follow(&image, &entry_points);
+        // Jump tables behind a PCHL are printed as pointers, unless the map
+        // already says what's there.
+        for (start, end) in traced.table_ranges() {
+            let _ = map.add(map::Range {
+                start,
+                end,
+                kind: Kind::Pointers,
+                note: "jump table".to_string(),
+            });
+        }
+        listing::traced(&image, &traced.instructions)
+    } else {
+        listing::linear(&image)
+    };
+    let mut lines = listing::mapped(&image, &map, &lines);
+    let platform = options.platform.clone().unwrap_or_default();
+    if options.strings {
+        let charset = options
+            .charset
+            .clone()
+            .or_else(|| platform.charset.clone())
+            .unwrap_or_default();
+        lines = strings::find(&lines, &map, &charset, options.min_string);
+    }
+    let mut labels = if options.labels {
+        labels::generate(&lines)
+    } else {
+        Labels::new()
+    };
+    for port in &platform.ports {
+        labels.insert_port(port.direction, port.number, port.name.clone());
+    }
+    for filename in &options.symbols {
+        let text = fs::read_to_string(filename)
+            .unwrap_or_else(|error| file_error(filename.as_ref(), error));
+        match symbols::parse(&text) {
+            Ok(symbols) => {
+                for (address, name) in symbols {
+                    labels.insert_symbol(address, name).unwrap_or_else(|error| {
+                        eprintln!("{}: {}", filename, error);
+                        process::exit(1);
+                    });
+                }
+            }
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                process::exit(1);
             }
-            "ff" => {
-                // CALL $38
-                println!("ff: RST 7");
-                position += 1;
+        }
+    }
+    let bdos_calls = if platform.bdos {
+        cpm::bdos_calls(&lines)
+    } else {
+        Default::default()
+    };
+    if options.verify {
+        verify(&image, &lines, &labels);
+    }
+    if options.format == Format::Asm {
+        let stdout = io::stdout();
+        let syntax = options.syntax(Syntax::assembler());
+        source::write_with_syntax(&mut stdout.lock(), &lines, &labels, &syntax)
+            .unwrap_or_else(|error| output_error(error));
+        return;
+    }
+    if options.format == Format::Json || options.format == Format::JsonLines {
+        // The same comments as the listing, less the ones the JSON has
+        // fields for.
+        let mut comments: BTreeMap<u16, Vec<String>> = BTreeMap::new();
+        for line in &lines {
+            let mut said = Vec::new();
+            match *line {
+                Line::Instruction(ref instruction) => {
+                    said.extend(bdos_calls.get(&instruction.address).cloned());
+                    said.extend(platform.comments(instruction));
+                }
+                Line::Data { ref comment, .. } => said.extend(comment.clone()),
+            }
+            match map.range(line.address()) {
+                Some(range) if range.start == line.address() && !range.note.is_empty() => {
+                    said.push(range.note.clone())
+                }
+                _ => {}
+            }
+            if !said.is_empty() {
+                comments.insert(line.address(), said);
             }
+        }
+        let style = if options.format == Format::Json {
+            json::Style::Document
+        } else {
+            json::Style::Lines
+        };
+        let stdout = io::stdout();
+        json::write(&mut stdout.lock(), &lines, &labels, &comments, style)
+            .unwrap_or_else(|error| output_error(error));
+        return;
+    }
+    if options.format == Format::Dot
+        || options.format == Format::Tree
+        || options.format == Format::CallGraph
+    {
+        let blocks = blocks::blocks(&lines);
+        // Wherever the code starts: the origin, the RST vectors and any
+        // entry points we were given.
+        let mut roots = trace::default_entry_points(&image);
+        roots.extend(start);
+        roots.extend(&options.entry_points);
+        let mut seen = HashSet::new();
+        roots.retain(|&root| seen.insert(root));
+        let graph = calls::CallGraph::new(&blocks, &roots);
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let written = match options.format {
+            Format::Tree => graph.write_tree(&mut out, &labels),
+            Format::CallGraph => graph.write_dot(&mut out, &labels),
             _ => {
-                println!("Unimplemented instruction: {}", hex);
-                position += 9999;
-            }
+                // Every routine, unless we were asked for particular ones.
+                let entries = if !options.routines.is_empty() {
+                    options.routines.clone()
+                } else {
+                    graph.routines.keys().cloned().collect()
+                };
+                let syntax = options.syntax(Syntax::default());
+                blocks::write_dot(&mut out, &blocks, &entries, &labels, &syntax)
+            }
+        };
+        written.unwrap_or_else(|error| output_error(error));
+        return;
+    }
+    let references = if options.xref {
+        xref::references(&lines)
+    } else {
+        Default::default()
+    };
+    let syntax = options.syntax(Syntax::default());
+    // Running T-state total for the current basic block, which starts again
+    // at every label and after anything that jumps, calls or returns.
+    let mut block_cycles = 0u32;
+    for line in &lines {
+        if let Some(name) = labels.get(line.address()) {
+            // Only who gets here, reads and writes go in the report at the end.
+            let callers: Vec<xref::Reference> = references
+                .get(&line.address())
+                .map_or(&[][..], |list| &list[..])
+                .iter()
+                .filter(|reference| {
+                    reference.access == xref::Access::Call || reference.access == xref::Access::Jump
+                })
+                .cloned()
+                .collect();
+            if callers.is_empty() {
+                println!("{}:", name);
+            } else {
+                println!("{}: ; {}", name, xref::describe(&callers));
+            }
+            block_cycles = 0;
+        }
+        if options.offsets {
+            print!("{}  ", offset(&loaded, line.address()));
         }
-        if position == end_position {
-            println!("Successfully parsed file");
+        print!("{:x}     ", line.address());
+        let mut comments = Vec::new();
+        let text = match *line {
+            Line::Instruction(ref instruction) => {
+                if instruction.undocumented {
+                    comments.push("undocumented".to_string());
+                }
+                if let Some(call) = bdos_calls.get(&instruction.address) {
+                    comments.push(call.clone());
+                }
+                comments.extend(platform.comments(instruction));
+                if options.cycles {
+                    let timing = instruction.timing();
+                    block_cycles += u32::from(timing.cycles);
+                    match timing.taken {
+                        Some(taken) => comments.push(format!(
+                            "{}/{} T, block {}",
+                            timing.cycles, taken, block_cycles
+                        )),
+                        None => {
+                            comments.push(format!("{} T, block {}", timing.cycles, block_cycles))
+                        }
+                    }
+                    if instruction.flow() != Flow::Next {
+                        block_cycles = 0;
+                    }
+                }
+                format!(
+                    "{:02x}: {}",
+                    instruction.opcode,
+                    syntax.instruction(instruction, Some(&labels))
+                )
+            }
+            Line::Data {
+                ref bytes,
+                format,
+                ref warning,
+                ref comment,
+                ..
+            } => {
+                block_cycles = 0;
+                if let Some(ref warning) = *warning {
+                    comments.push(warning.clone());
+                    eprintln!("warning: {}", warning);
+                }
+                comments.extend(comment.clone());
+                syntax.data(bytes, format, Some(&labels))
+            }
+        };
+        match map.range(line.address()) {
+            Some(range) if range.start == line.address() && !range.note.is_empty() => {
+                comments.push(range.note.clone())
+            }
+            _ => {}
         }
+        if comments.is_empty() {
+            println!("{}", text);
+        } else if options.cycles {
+            // Line the timings up so they're easy to run an eye down.
+            println!("{:<24} ; {}", text, comments.join("; "));
+        } else {
+            println!("{} ; {}", text, comments.join("; "));
+        }
+    }
+    if let Some((start, end)) = options.cycle_range {
+        print_cycle_range(&lines, start, end);
     }
-    // for i in buffer.iter() {
-    // }
+    if options.xref {
+        print_references(&references, &labels);
+    }
+    println!("Successfully parsed file");
 }
-
@@ -0,0 +1,164 @@
+// A region map says what parts of memory are, for when a linear sweep or a
+// trace can't work it out: that 0x1a00-0x1bff is a sprite table, say. It's
+// one range a line:
+//
+//     0x0000-0x19ff  code
+//     0x1a00-0x1bff  bytes     sprites
+//     0x1c00-0x1c1f  pointers  message table
+//     0x1c20-0x1fff  ascii
+//
+// Anything after the kind is a note that goes on the range's first line.
+
+use number;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Code,
+    Bytes,
+    // 16 bit numbers, low byte first.
+    Words,
+    // Addresses, printed as labels where there is one.
+    Pointers,
+    Ascii,
+    // Not worked out yet. Printed as bytes so nothing guesses it's code.
+    Unknown,
+}
+
+impl Kind {
+    fn from_name(name: &str) -> Option<Kind> {
+        let kind = match &name.to_lowercase() as &str {
+            "code" => Kind::Code,
+            "bytes" => Kind::Bytes,
+            "words" => Kind::Words,
+            "pointers" => Kind::Pointers,
+            "ascii" | "text" => Kind::Ascii,
+            "unknown" => Kind::Unknown,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+// Start and end both included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub start: u16,
+    pub end: u16,
+    pub kind: Kind,
+    pub note: String,
+}
+
+impl Range {
+    pub fn contains(&self, address: u16) -> bool {
+        address >= self.start && address <= self.end
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapError {
+    // 1 based line number in the file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for MapError {}
+
+// Ranges are kept in address order and never overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Map {
+    ranges: Vec<Range>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
+    pub fn range(&self, address: u16) -> Option<&Range> {
+        self.ranges.iter().find(|range| range.contains(address))
+    }
+
+    // The first range that starts after `address`.
+    pub fn next_range(&self, address: u16) -> Option<&Range> {
+        self.ranges.iter().find(|range| range.start > address)
+    }
+
+    pub fn add(&mut self, range: Range) -> Result<(), String> {
+        if let Some(other) = self
+            .ranges
+            .iter()
+            .find(|other| range.start <= other.end && range.end >= other.start)
+        {
+            return Err(format!(
+                "{:04x}-{:04x} overlaps {:04x}-{:04x}",
+                range.start, range.end, other.start, other.end
+            ));
+        }
+        let index = self
+            .ranges
+            .iter()
+            .position(|other| other.start > range.start)
+            .unwrap_or(self.ranges.len());
+        self.ranges.insert(index, range);
+        Ok(())
+    }
+}
+
+pub fn parse(text: &str) -> Result<Map, MapError> {
+    let mut map = Map::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| MapError {
+            line: index + 1,
+            message,
+        };
+        let line = match line.find(['#', ';']) {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let mut words = line.split_whitespace();
+        let span = match words.next() {
+            Some(span) => span,
+            None => continue,
+        };
+        let (start, end) = span
+            .find('-')
+            .and_then(|dash| {
+                Some((
+                    number::address(&span[..dash])?,
+                    number::address(&span[dash + 1..])?,
+                ))
+            })
+            .filter(|&(start, end)| start <= end)
+            .ok_or_else(|| error(format!("Bad address range {}", span)))?;
+        let kind = words
+            .next()
+            .ok_or_else(|| error("Missing what the range is".to_string()))?;
+        let kind = Kind::from_name(kind).ok_or_else(|| {
+            error(format!(
+                "Unknown kind {}, expected code, bytes, words, pointers, ascii or unknown",
+                kind
+            ))
+        })?;
+        let note = words.collect::<Vec<&str>>().join(" ");
+        map.add(Range {
+            start,
+            end,
+            kind,
+            note,
+        })
+        .map_err(&error)?;
+    }
+    Ok(map)
+}
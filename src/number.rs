@@ -0,0 +1,52 @@
+// Numbers as people write them for the 8080. `parse` reads them the way an
+// assembler does, upper or lower case, any of
+//
+//     0x1234  $1234  1234H   hex
+//     1010B                  binary
+//     17Q  17O               octal
+//     1234  1234D            decimal
+//
+// There's no sign, a minus is up to whatever's reading the number. In the
+// assembler 1234H style hex that starts with a letter needs a 0 in front,
+// like 0FFH, or it would be a name.
+//
+// `address` is for things that are only ever an address, like `--origin`
+// or a map file's ranges. Those are hex, so one copied from the listing,
+// like `1a00`, reads the same as `0x1a00`, `$1a00` or `1a00H`.
+
+pub fn parse(text: &str) -> Option<i64> {
+    let text = text.to_uppercase();
+    let (digits, radix) = if let Some(digits) = text.strip_prefix("0X") {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_prefix('$') {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_suffix('H') {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_suffix('B') {
+        (digits, 2)
+    } else if let Some(digits) = text.strip_suffix('Q').or_else(|| text.strip_suffix('O')) {
+        (digits, 8)
+    } else if let Some(digits) = text.strip_suffix('D') {
+        (digits, 10)
+    } else {
+        (&text[..], 10)
+    };
+    // from_str_radix would take a + or - too.
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    i64::from_str_radix(digits, radix).ok()
+}
+
+pub fn address(text: &str) -> Option<u16> {
+    let upper = text.to_uppercase();
+    let digits = upper
+        .strip_prefix("0X")
+        .or_else(|| upper.strip_prefix('$'))
+        .or_else(|| upper.strip_suffix('H'))
+        .unwrap_or(&upper);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
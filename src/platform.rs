@@ -0,0 +1,386 @@
+// What's known about the machine the code runs on: what the I/O ports are
+// wired to and what lives where in memory. The listing uses it to say what
+// an IN, OUT or memory access is actually touching.
+
+use instruction::{Instruction, Mnemonic, Operand};
+use number;
+use std::error::Error;
+use std::fmt;
+use strings::Charset;
+use symbols;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+// Hardware that `--run` knows how to emulate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Machine {
+    Invaders,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Port {
+    pub direction: Direction,
+    pub number: u8,
+    pub name: String,
+    pub description: String,
+}
+
+// A named stretch of memory, start and end both included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Area {
+    pub start: u16,
+    pub end: u16,
+    pub name: String,
+}
+
+impl Area {
+    pub fn contains(&self, address: u16) -> bool {
+        address >= self.start && address <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Platform {
+    pub name: String,
+    // Where code gets loaded if nothing says otherwise.
+    pub origin: u16,
+    pub ports: Vec<Port>,
+    pub areas: Vec<Area>,
+    // Calls to 0x0005 are CP/M BDOS calls.
+    pub bdos: bool,
+    // The machine's own character codes, for finding its messages.
+    pub charset: Option<Charset>,
+    // What `--run` runs the code on, if it's more than a CP/M program.
+    pub machine: Option<Machine>,
+}
+
+impl Platform {
+    // The profiles that come with the disassembler, which are written the
+    // same way as a platform file. See platforms/ for them.
+    pub fn builtin(name: &str) -> Option<Platform> {
+        let text = match name {
+            "invaders" => include_str!("../platforms/invaders.toml"),
+            "altair" => include_str!("../platforms/altair.toml"),
+            "imsai" => include_str!("../platforms/imsai.toml"),
+            "cpm" => include_str!("../platforms/cpm.toml"),
+            _ => return None,
+        };
+        Some(parse(text).unwrap())
+    }
+
+    pub fn builtin_names() -> &'static [&'static str] {
+        &["invaders", "altair", "imsai", "cpm"]
+    }
+
+    pub fn port(&self, direction: Direction, number: u8) -> Option<&Port> {
+        self.ports
+            .iter()
+            .find(|port| port.direction == direction && port.number == number)
+    }
+
+    pub fn area(&self, address: u16) -> Option<&Area> {
+        self.areas.iter().find(|area| area.contains(address))
+    }
+
+    // Notes for the listing about what an instruction's port or memory
+    // operand is. The port's name goes in place of its number (see
+    // `Labels::insert_port`), so this is just its description. Jumps and
+    // calls are left alone since they always go to code.
+    pub fn comments(&self, instruction: &Instruction) -> Vec<String> {
+        let mut comments = Vec::new();
+        for operand in &instruction.operands {
+            match *operand {
+                Operand::Port(number) => {
+                    let direction = if instruction.mnemonic == Mnemonic::In {
+                        Direction::In
+                    } else {
+                        Direction::Out
+                    };
+                    match self.port(direction, number) {
+                        Some(port) if !port.description.is_empty() => {
+                            comments.push(port.description.clone())
+                        }
+                        _ => {}
+                    }
+                }
+                Operand::Address(address) | Operand::Word(address)
+                    if instruction.target().is_none() =>
+                {
+                    if let Some(area) = self.area(address) {
+                        comments.push(area.name.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        comments
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformError {
+    // 1 based line number in the file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PlatformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for PlatformError {}
+
+enum Value {
+    Text(String),
+    Number(i64),
+    Flag(bool),
+    List(Vec<String>),
+}
+
+// Reads a platform definition, which is a small part of TOML:
+//
+//     name = "Space Invaders"
+//     origin = 0x0000
+//
+//     [out]
+//     0x03 = ["SOUND1", "sound bits"]
+//
+//     [memory]
+//     0x2400-0x3fff = "video RAM"
+//
+// Ports go in [in], [out], or [ports] for both, as a name or a name and a
+// description. `bdos = true` names CP/M BDOS calls, and `machine =
+// "invaders"` says `--run` should run it on the Space Invaders board. Numbers
+// can be written any way `number::parse` takes, and memory ranges any way
+// `number::address` does. A [charset] section gives the characters for a byte
+// or a range of them, one character a byte:
+//
+//     [charset]
+//     0x00-0x19 = "ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+//     0x26 = " "
+pub fn parse(text: &str) -> Result<Platform, PlatformError> {
+    let mut platform = Platform::default();
+    let mut section = String::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| PlatformError {
+            line: index + 1,
+            message,
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            section = match line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                Some(name @ "in")
+                | Some(name @ "out")
+                | Some(name @ "ports")
+                | Some(name @ "memory")
+                | Some(name @ "charset") => name.to_string(),
+                _ => return Err(error(format!("Unknown section {}", line))),
+            };
+            continue;
+        }
+        let equals = line
+            .find('=')
+            .ok_or_else(|| error("Expected key = value".to_string()))?;
+        let key = line[..equals].trim();
+        let value = parse_value(line[equals + 1..].trim()).map_err(&error)?;
+        match (&section[..], key, value) {
+            ("", "name", Value::Text(name)) => platform.name = name,
+            ("", "origin", Value::Number(origin)) if origin <= 0xffff => {
+                platform.origin = origin as u16
+            }
+            ("", "bdos", Value::Flag(bdos)) => platform.bdos = bdos,
+            ("", "machine", Value::Text(machine)) => {
+                platform.machine = match &machine[..] {
+                    "invaders" => Some(Machine::Invaders),
+                    _ => return Err(error(format!("Unknown machine {}", machine))),
+                }
+            }
+            ("", _, _) => return Err(error(format!("Bad setting {}", key))),
+            ("memory", _, Value::Text(name)) => {
+                let range = key.find('-').and_then(|dash| {
+                    Some((
+                        number::address(key[..dash].trim())?,
+                        number::address(key[dash + 1..].trim())?,
+                    ))
+                });
+                match range {
+                    Some((start, end)) if start <= end => {
+                        platform.areas.push(Area { start, end, name })
+                    }
+                    _ => return Err(error(format!("Bad address range {}", key))),
+                }
+            }
+            ("memory", _, _) => return Err(error("Memory needs a name".to_string())),
+            ("charset", _, Value::Text(chars)) => {
+                let (start, end) = match key.find('-') {
+                    Some(dash) => (
+                        number::parse(key[..dash].trim()),
+                        number::parse(key[dash + 1..].trim()),
+                    ),
+                    None => (number::parse(key), number::parse(key)),
+                };
+                let (start, end) = match (start, end) {
+                    (Some(start), Some(end)) if start <= end && end <= 0xff => (start, end),
+                    _ => return Err(error(format!("Bad byte range {}", key))),
+                };
+                let count = chars.chars().count() as i64;
+                if count != end - start + 1 {
+                    return Err(error(format!(
+                        "{} is {} bytes but there are {} characters",
+                        key,
+                        end - start + 1,
+                        count
+                    )));
+                }
+                let charset = platform.charset.get_or_insert_with(Charset::new);
+                for (byte, c) in (start..=end).zip(chars.chars()) {
+                    charset.insert(byte as u8, c);
+                }
+            }
+            ("charset", _, _) => {
+                return Err(error(
+                    "A character set entry needs its characters".to_string(),
+                ))
+            }
+            (section, _, value) => {
+                let number = match number::parse(key) {
+                    Some(number) if number <= 0xff => number as u8,
+                    _ => return Err(error(format!("Bad port number {}", key))),
+                };
+                let (name, description) = match value {
+                    Value::Text(name) => (name, String::new()),
+                    Value::List(ref parts) if parts.len() == 1 => (parts[0].clone(), String::new()),
+                    Value::List(ref parts) if parts.len() == 2 => {
+                        (parts[0].clone(), parts[1].clone())
+                    }
+                    _ => {
+                        return Err(error(
+                            "A port needs a name, or a name and a description".to_string(),
+                        ))
+                    }
+                };
+                // The names get used as operands, so they have to be
+                // something an assembler would take.
+                if !symbols::is_name(&name) {
+                    return Err(error(format!("{} can't be used as a name", name)));
+                }
+                if let Some(other) = platform
+                    .ports
+                    .iter()
+                    .find(|port| port.name == name && port.number != number)
+                {
+                    return Err(error(format!(
+                        "{} is already port {:02x}",
+                        name, other.number
+                    )));
+                }
+                let directions: &[Direction] = match section {
+                    "in" => &[Direction::In],
+                    "out" => &[Direction::Out],
+                    _ => &[Direction::In, Direction::Out],
+                };
+                for &direction in directions {
+                    match platform.port(direction, number) {
+                        Some(other) if other.name != name => {
+                            return Err(error(format!(
+                                "Port {:02x} is already {}",
+                                number, other.name
+                            )))
+                        }
+                        // Given again with the same name, which changes nothing.
+                        Some(_) => continue,
+                        None => {}
+                    }
+                    platform.ports.push(Port {
+                        direction,
+                        number,
+                        name: name.clone(),
+                        description: description.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(platform)
+}
+
+// Cuts off a # comment, as long as it isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    if text == "true" || text == "false" {
+        return Ok(Value::Flag(text == "true"));
+    }
+    if let Some(number) = number::parse(text) {
+        return Ok(Value::Number(number));
+    }
+    if let Some(items) = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+    {
+        let mut strings = Vec::new();
+        let mut rest = items.trim();
+        while !rest.is_empty() {
+            let (string, after) = parse_string(rest)?;
+            strings.push(string);
+            rest = after.trim_start();
+            rest = match rest.strip_prefix(',') {
+                Some(after) => after.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return Err("Expected a comma between strings".to_string()),
+            };
+        }
+        return Ok(Value::List(strings));
+    }
+    match parse_string(text)? {
+        (string, "") => Ok(Value::Text(string)),
+        _ => Err("Unexpected text after the string".to_string()),
+    }
+}
+
+// A double quoted string with \" and \\ escapes, and whatever comes after it.
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let body = text
+        .strip_prefix('"')
+        .ok_or_else(|| format!("Expected a value, not {}", text))?;
+    let mut string = String::new();
+    let mut chars = body.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((string, body[index + 1..].trim_start())),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => string.push('\n'),
+                Some((_, 't')) => string.push('\t'),
+                Some((_, c)) => string.push(c),
+                None => break,
+            },
+            c => string.push(c),
+        }
+    }
+    Err("String doesn't end".to_string())
+}
@@ -0,0 +1,92 @@
+// Just enough PNG to save a screenshot: 8 bit greyscale, with the image
+// data in uncompressed ("stored") deflate blocks so no compressor is needed.
+
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+// The most a stored deflate block can hold.
+const STORED_BLOCK: usize = 0xffff;
+
+// `pixels` is one byte per pixel, row by row from the top left.
+pub fn write_greyscale<W: Write>(
+    out: &mut W,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> io::Result<()> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, colour type 0 (greyscale), then the standard compression,
+    // filter and no interlacing.
+    header.extend_from_slice(&[8, 0, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    // Every row starts with its filter type, 0 for none.
+    let mut raw = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    // The CRC covers the chunk type and data but not the length.
+    let crc = !crc32(crc32(!0, kind), data);
+    out.write_all(&crc.to_be_bytes())
+}
+
+// A zlib stream of stored deflate blocks: the zlib header, each block with
+// its length and that length's complement, and an Adler-32 of the data.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // 32K window, no preset dictionary, and a check value making the
+    // header a multiple of 31.
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        stream.push(last as u8);
+        let length = block.len() as u16;
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+// Bit at a time rather than with a table, which is plenty fast for a screenshot.
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % MODULUS;
+        b = (b + a) % MODULUS;
+    }
+    b << 16 | a
+}
@@ -0,0 +1,159 @@
+// Writes the listing out as Intel 8080 assembly source that an assembler
+// like asl or zmac can turn straight back into the original bytes.
+
+use instruction::{Instruction, Operand};
+use labels::Labels;
+use listing::{self, DataFormat, Line};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use syntax::Syntax;
+
+// Label column width, instructions line up after it.
+const INDENT: &str = "        ";
+
+pub fn write<W: Write>(out: &mut W, lines: &[Line], labels: &Labels) -> io::Result<()> {
+    write_with_syntax(out, lines, labels, &Syntax::assembler())
+}
+
+// The same, written some other way, e.g. with Zilog mnemonics for a Z80
+// assembler.
+pub fn write_with_syntax<W: Write>(
+    out: &mut W,
+    lines: &[Line],
+    labels: &Labels,
+    syntax: &Syntax,
+) -> io::Result<()> {
+    let equates = equates(lines, labels, syntax);
+    for (name, value) in &equates {
+        writeln!(out, "{:<7} {:<8}{}", name, syntax.directive("EQU"), value)?;
+    }
+    if !equates.is_empty() {
+        writeln!(out)?;
+    }
+    // Where the assembler will be after the previous line, so we know when
+    // there's a gap that needs a new ORG.
+    let mut next = None;
+    for line in lines {
+        if next != Some(line.address() as usize) {
+            if next.is_some() {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "{}{:<8}{}",
+                INDENT,
+                syntax.directive("ORG"),
+                syntax.word(line.address())
+            )?;
+            writeln!(out)?;
+        }
+        next = Some(line.address() as usize + line.length());
+        if let Some(name) = labels.get(line.address()) {
+            writeln!(out, "{}:", name)?;
+        }
+        match *line {
+            Line::Instruction(ref instruction) if instruction.undocumented => {
+                // Assemblers only know the documented opcodes, so the bytes
+                // have to go in by hand to come out the same.
+                writeln!(
+                    out,
+                    "{}{:<8}{} ; {} (undocumented)",
+                    INDENT,
+                    syntax.directive("DB"),
+                    instruction_bytes(instruction, syntax),
+                    statement(instruction, labels, syntax)
+                )?;
+            }
+            Line::Instruction(ref instruction) => {
+                writeln!(out, "{}{}", INDENT, statement(instruction, labels, syntax))?;
+            }
+            Line::Data {
+                ref bytes,
+                format,
+                ref warning,
+                ref comment,
+                ..
+            } => {
+                let (directive, items) = syntax.data_parts(bytes, format, Some(labels));
+                let statement = format!("{:<8}{}", directive, items.join(","));
+                match warning.as_ref().or(comment.as_ref()) {
+                    Some(comment) => writeln!(out, "{}{} ; {}", INDENT, statement, comment)?,
+                    None => writeln!(out, "{}{}", INDENT, statement)?,
+                }
+            }
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "{}{}", INDENT, syntax.directive("END"))
+}
+
+// Names used as operands that don't mark a line, so the assembler has to be
+// told what they are: port names, and symbols for addresses outside the
+// code or in the middle of an instruction.
+fn equates<'a>(lines: &[Line], labels: &'a Labels, syntax: &Syntax) -> BTreeMap<&'a str, String> {
+    let starts: HashSet<u16> = lines.iter().map(|line| line.address()).collect();
+    let mut names = BTreeMap::new();
+    for line in lines {
+        if let Line::Data {
+            ref bytes,
+            format: DataFormat::Pointers,
+            ..
+        } = *line
+        {
+            for address in listing::words(bytes) {
+                match labels.get(address) {
+                    Some(name) if !starts.contains(&address) => {
+                        names.insert(name, syntax.word(address));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Line::Instruction(ref instruction) = *line {
+            for operand in &instruction.operands {
+                let name = match instruction.label_for(operand, labels) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                match *operand {
+                    Operand::Port(number) => {
+                        names.insert(name, syntax.byte(number));
+                    }
+                    Operand::Address(address) | Operand::Word(address)
+                        if !starts.contains(&address) =>
+                    {
+                        names.insert(name, syntax.word(address));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    names
+}
+
+// The instruction the way an assembler wants it, e.g. `LXI     H,1234H`.
+fn statement(instruction: &Instruction, labels: &Labels, syntax: &Syntax) -> String {
+    let (mnemonic, operands) = syntax.instruction_parts(instruction, Some(labels));
+    if operands.is_empty() {
+        mnemonic
+    } else {
+        format!("{:<8}{}", mnemonic, operands.join(","))
+    }
+}
+
+fn instruction_bytes(instruction: &Instruction, syntax: &Syntax) -> String {
+    let mut bytes = vec![syntax.byte(instruction.opcode)];
+    for operand in &instruction.operands {
+        match *operand {
+            Operand::Byte(value) | Operand::Port(value) => bytes.push(syntax.byte(value)),
+            // Low byte first, the way it sits in memory.
+            Operand::Word(value) | Operand::Address(value) => {
+                bytes.push(syntax.byte(value as u8));
+                bytes.push(syntax.byte((value >> 8) as u8));
+            }
+            _ => {}
+        }
+    }
+    bytes.join(",")
+}
@@ -0,0 +1,199 @@
+// Finds the messages in a ROM, like "INSERT COIN", among the bytes that
+// aren't code, so they come out as `DB "INSERT COIN"` and not a page of DBs.
+// A string is a run of characters at least so long, which can end in one of
+// the usual terminators: a 0 byte, a CP/M `$`, or its last character with the
+// top bit set.
+//
+// Machines that draw their own text, like Space Invaders, don't use ASCII,
+// so the characters can come from a table instead. Those strings stay as
+// bytes, so they still assemble the same, with the text in a comment.
+
+use listing::{self, DataFormat, Line, TEXT_PER_LINE};
+use map::{Kind, Map};
+
+// Shorter runs than this are too likely to be numbers that just happen to be
+// printable.
+pub const MIN_LENGTH: usize = 4;
+
+// What character each byte is, if it's one at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Charset {
+    chars: Vec<Option<char>>,
+    // Whether it's exactly `Charset::ascii()`, worked out as it's filled in
+    // since the string search asks about every byte.
+    ascii: bool,
+}
+
+impl Charset {
+    // No characters at all, to be filled in with `insert`.
+    pub fn new() -> Charset {
+        Charset {
+            chars: vec![None; 256],
+            ascii: false,
+        }
+    }
+
+    // The printable ASCII characters, space to ~.
+    pub fn ascii() -> Charset {
+        Charset {
+            chars: (0..=255u8).map(ascii_char).collect(),
+            ascii: true,
+        }
+    }
+
+    pub fn insert(&mut self, byte: u8, c: char) {
+        self.chars[byte as usize] = Some(c);
+        self.ascii = (0..=255u8).all(|byte| self.chars[byte as usize] == ascii_char(byte));
+    }
+
+    pub fn get(&self, byte: u8) -> Option<char> {
+        self.chars[byte as usize]
+    }
+
+    pub fn is_ascii(&self) -> bool {
+        self.ascii
+    }
+
+    // The text the bytes spell out, leaving out anything that isn't a
+    // character and reading a top bit terminator as the character under it.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .filter_map(|&byte| self.get(byte).or_else(|| self.get(byte & 0x7f)))
+            .collect()
+    }
+
+    // How many characters there are at the start of `bytes`. CP/M prints up
+    // to a $, so whatever comes after one is something else.
+    fn run(&self, bytes: &[u8]) -> usize {
+        let mut length = 0;
+        for &byte in bytes {
+            let c = match self.get(byte) {
+                Some(c) => c,
+                None => break,
+            };
+            length += 1;
+            if c == '$' && self.is_ascii() {
+                break;
+            }
+        }
+        length
+    }
+
+    // How long the string at the start of `bytes` is, terminator and all.
+    fn string_length(&self, bytes: &[u8], min_length: usize) -> Option<usize> {
+        let length = self.run(bytes);
+        if bytes[..length].ends_with(b"$") && self.is_ascii() {
+            return Some(length).filter(|&length| length >= min_length);
+        }
+        match bytes.get(length) {
+            // The 0 doesn't count towards the length.
+            Some(&0) if length >= min_length => Some(length + 1),
+            Some(&byte)
+                if byte & 0x80 != 0
+                    && self.get(byte & 0x7f).is_some()
+                    && length + 1 >= min_length =>
+            {
+                Some(length + 1)
+            }
+            _ if length >= min_length => Some(length),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Charset {
+    fn default() -> Charset {
+        Charset::ascii()
+    }
+}
+
+// What a byte is in ASCII, if it's printable.
+fn ascii_char(byte: u8) -> Option<char> {
+    if (0x20..0x7f).contains(&byte) {
+        Some(byte as char)
+    } else {
+        None
+    }
+}
+
+// Redoes the data lines with any strings in them split out. Only lines of
+// plain bytes are looked at, and not ones in a range the map has already
+// said is bytes.
+pub fn find(lines: &[Line], map: &Map, charset: &Charset, min_length: usize) -> Vec<Line> {
+    let searchable = |line: &Line| match *line {
+        Line::Data {
+            format: DataFormat::Bytes,
+            warning: None,
+            comment: None,
+            address,
+            ..
+        } => map
+            .range(address)
+            .is_none_or(|range| range.kind == Kind::Unknown),
+        _ => false,
+    };
+    let mut found = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        if !searchable(&lines[index]) {
+            found.push(lines[index].clone());
+            index += 1;
+            continue;
+        }
+        // Join up the lines that follow on from each other, since a string
+        // can run across the 8 byte lines.
+        let origin = lines[index].address();
+        let mut bytes = Vec::new();
+        while index < lines.len()
+            && searchable(&lines[index])
+            && lines[index].address() as usize == origin as usize + bytes.len()
+        {
+            if let Line::Data {
+                bytes: ref more, ..
+            } = lines[index]
+            {
+                bytes.extend_from_slice(more);
+            }
+            index += 1;
+        }
+        strings(&bytes, origin, charset, min_length, &mut found);
+    }
+    found
+}
+
+fn strings(bytes: &[u8], origin: u16, charset: &Charset, min_length: usize, lines: &mut Vec<Line>) {
+    // Where the bytes that aren't a string start.
+    let mut plain = 0;
+    let mut position = 0;
+    while position < bytes.len() {
+        let length = match charset.string_length(&bytes[position..], min_length) {
+            Some(length) => length,
+            None => {
+                // Skip the whole run, or the end of a short one would turn
+                // up as a string of its own.
+                position += charset.run(&bytes[position..]).max(1);
+                continue;
+            }
+        };
+        listing::data_lines(bytes, origin, plain, position, DataFormat::Bytes, lines);
+        let end = position + length;
+        if charset.is_ascii() {
+            listing::data_lines(bytes, origin, position, end, DataFormat::Text, lines);
+        } else {
+            for start in (position..end).step_by(TEXT_PER_LINE) {
+                let line = &bytes[start..end.min(start + TEXT_PER_LINE)];
+                lines.push(Line::Data {
+                    address: origin + start as u16,
+                    bytes: line.to_vec(),
+                    format: DataFormat::Bytes,
+                    warning: None,
+                    comment: Some(format!("\"{}\"", charset.decode(line))),
+                });
+            }
+        }
+        position = end;
+        plain = end;
+    }
+    listing::data_lines(bytes, origin, plain, bytes.len(), DataFormat::Bytes, lines);
+}
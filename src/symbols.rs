@@ -0,0 +1,202 @@
+// Reads symbol files, so names someone has already worked out for a ROM's
+// routines, variables and hardware can go in place of the generated labels
+// and bare addresses. Takes plain `NAME = 0x1234` (or `NAME EQU 1234H`)
+// lines, and the symbol tables assemblers write out:
+//
+//     START       0100    LOOP        0105      zmac and most others
+//     0100 START  0105 LOOP                     M80/L80 style .SYM files
+//     START :  100 C |  LOOP :  105 C |         asl listings
+//     START 100 4 0                             asl's MAP file
+
+use labels;
+use number;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    // 1 based line number in the file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SymbolError {}
+
+// Names by address. When an address has more than one name the first
+// one in the file wins, as it does across files (see `Labels::insert_symbol`).
+// A name can't be used for two addresses, or look like the label that would
+// be generated for another one, since the asm output would define it twice.
+pub fn parse(text: &str) -> Result<BTreeMap<u16, String>, SymbolError> {
+    let mut symbols = BTreeMap::new();
+    // Where each name has been put, by its upper case form since assemblers
+    // don't care about case.
+    let mut addresses: HashMap<String, u16> = HashMap::new();
+    // Inside the symbol section of an asl MAP file.
+    let mut asl_map = false;
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| SymbolError {
+            line: index + 1,
+            message,
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("Symbols in Segment") {
+            asl_map = true;
+            continue;
+        }
+        let found = if let Some((name, value, equ)) = assignment(line) {
+            // Someone wrote this by hand, so say what's wrong with it.
+            if !is_name(name) {
+                return Err(error(format!("{} can't be used as a name", name)));
+            }
+            // EQU comes from assembler source, so its numbers are read like
+            // the assembler's.
+            let address = if equ {
+                number::parse(value).and_then(|value| u16::try_from(value).ok())
+            } else {
+                number::address(value)
+            };
+            let address = address.ok_or_else(|| error(format!("Bad address {}", value)))?;
+            vec![(address, name)]
+        } else if asl_map {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next().and_then(hex)) {
+                (Some(name), Some(address)) if is_name(name) => vec![(address, name)],
+                _ => {
+                    asl_map = false;
+                    Vec::new()
+                }
+            }
+        } else if line.contains('|') || line.contains(" : ") {
+            asl_table(line)
+        } else {
+            table(line)
+        };
+        for (address, name) in found {
+            match labels::generated_address(name) {
+                Some(other) if other != address => {
+                    return Err(error(format!(
+                        "{} is the name of the generated label for {:04x}",
+                        name, other
+                    )))
+                }
+                _ => {}
+            }
+            match *addresses.entry(name.to_uppercase()).or_insert(address) {
+                other if other != address => {
+                    return Err(error(format!("{} is already {:04x}", name, other)))
+                }
+                _ => {}
+            }
+            symbols.entry(address).or_insert_with(|| name.to_string());
+        }
+    }
+    Ok(symbols)
+}
+
+// `NAME = value`, `NAME EQU value` or `NAME: EQU value`, and whether it was
+// an EQU.
+fn assignment(line: &str) -> Option<(&str, &str, bool)> {
+    if let Some(equals) = line.find('=') {
+        let name = line[..equals].trim();
+        // Headings like asl's `symbol table (* = unused)` have an = too.
+        if !name.contains(char::is_whitespace) {
+            return Some((name, line[equals + 1..].trim(), false));
+        }
+    }
+    let mut words = line.split_whitespace();
+    let name = words.next()?.trim_end_matches(':');
+    let keyword = words.next()?;
+    let value = words.next()?;
+    if keyword.eq_ignore_ascii_case("EQU") && words.next().is_none() {
+        Some((name, value, true))
+    } else {
+        None
+    }
+}
+
+// asl puts its symbol table in columns split by `|`, each entry being
+// `NAME : VALUE TYPE`. Only code and integer symbols are addresses.
+fn asl_table(line: &str) -> Vec<(u16, &str)> {
+    line.split('|')
+        .filter_map(|entry| {
+            let colon = entry.find(" : ")?;
+            let name = entry[..colon].trim().trim_start_matches('*');
+            let mut rest = entry[colon + 3..].split_whitespace();
+            let address = hex(rest.next()?)?;
+            match rest.next() {
+                Some("C") | Some("I") | None if is_name(name) => Some((address, name)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// Pairs of name and 4 digit hex value, either way round. Lines that aren't
+// made entirely of pairs like that are headings and get skipped.
+fn table(line: &str) -> Vec<(u16, &str)> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.is_empty() || !words.len().is_multiple_of(2) {
+        return Vec::new();
+    }
+    let value_first = four_digits(words[0]).is_some() && four_digits(words[1]).is_none();
+    let mut pairs = Vec::new();
+    for pair in words.chunks(2) {
+        let (name, value) = if value_first {
+            (pair[1], pair[0])
+        } else {
+            (pair[0], pair[1])
+        };
+        match four_digits(value) {
+            Some(address) if is_name(name) => pairs.push((address, name)),
+            _ => return Vec::new(),
+        }
+    }
+    pairs
+}
+
+// Symbol tables print addresses as 4 hex digits, sometimes with an H on the
+// end or a ' to say it's relocatable.
+fn four_digits(word: &str) -> Option<u16> {
+    let digits = word.trim_end_matches('\'').trim_end_matches(['h', 'H']);
+    if digits.len() == 4 {
+        hex(digits)
+    } else {
+        None
+    }
+}
+
+fn hex(word: &str) -> Option<u16> {
+    u16::from_str_radix(word, 16).ok()
+}
+
+// Names end up in the asm output, so they have to be ones the assembler
+// takes, and not ones it would read as a register. Port names from a
+// platform file go through here too.
+pub fn is_name(word: &str) -> bool {
+    let name_char = |c: char| c.is_ascii_alphanumeric() || "_?@$".contains(c);
+    let register = matches!(
+        &word.to_uppercase() as &str,
+        "A" | "B" | "C" | "D" | "E" | "H" | "L" | "M" | "SP" | "PSW"
+    );
+    word.starts_with(|c: char| c.is_ascii_alphabetic() || "_?@".contains(c))
+        && word.chars().all(name_char)
+        && !register
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find([';', '#']) {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
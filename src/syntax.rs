@@ -0,0 +1,284 @@
+// How instructions and data are written out: the hex style, padding, case,
+// and whether it's Intel's 8080 mnemonics or Zilog's Z80 names for the same
+// instructions. The listing, the assembler source and the graphs all go
+// through here so they read the same way.
+
+use instruction::{Instruction, Mnemonic, Operand, Register, RegisterPair};
+use labels::Labels;
+use listing::{self, DataFormat, TextPiece};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hex {
+    // #1234, what the listing has always used
+    Hash,
+    // 0x1234
+    C,
+    // 1234H, with a 0 in front if it starts with a letter so it isn't taken
+    // for a name
+    Intel,
+    // $1234
+    Dollar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    // MOV A,M / LXI H,1234H / JNZ
+    Intel,
+    // LD A,(HL) / LD HL,1234H / JP NZ
+    Zilog,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syntax {
+    pub hex: Hex,
+    // Bytes always get two digits and words four.
+    pub pad: bool,
+    // Mnemonics, registers, directives, and the digits of 1234H style hex.
+    // Labels are left alone.
+    pub uppercase: bool,
+    pub dialect: Dialect,
+}
+
+impl Default for Syntax {
+    // The listing: `JMP #18d4` and `MVI B,#5`.
+    fn default() -> Syntax {
+        Syntax {
+            hex: Hex::Hash,
+            pad: false,
+            uppercase: true,
+            dialect: Dialect::Intel,
+        }
+    }
+}
+
+impl Syntax {
+    // What assemblers want: `JMP 18D4H` and `MVI B,05H`.
+    pub fn assembler() -> Syntax {
+        Syntax {
+            hex: Hex::Intel,
+            pad: true,
+            uppercase: true,
+            dialect: Dialect::Intel,
+        }
+    }
+
+    pub fn byte(&self, value: u8) -> String {
+        self.number(u32::from(value), 2)
+    }
+
+    pub fn word(&self, value: u16) -> String {
+        self.number(u32::from(value), 4)
+    }
+
+    fn number(&self, value: u32, digits: usize) -> String {
+        let digits = if self.pad { digits } else { 1 };
+        let digits = format!("{:0digits$x}", value, digits = digits);
+        match self.hex {
+            Hex::Hash => format!("#{}", digits),
+            Hex::C => format!("0x{}", digits),
+            Hex::Dollar => format!("${}", digits),
+            Hex::Intel if digits.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                self.case(&format!("0{}h", digits))
+            }
+            Hex::Intel => self.case(&format!("{}h", digits)),
+        }
+    }
+
+    fn case(&self, text: &str) -> String {
+        if self.uppercase {
+            text.to_uppercase()
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    // An assembler directive like ORG or DB, with Zilog's names for the data
+    // ones.
+    pub fn directive(&self, name: &str) -> String {
+        let name = match (self.dialect, name) {
+            (Dialect::Zilog, "DB") => "DEFB",
+            (Dialect::Zilog, "DW") => "DEFW",
+            _ => name,
+        };
+        self.case(name)
+    }
+
+    // An operand on its own, without any label.
+    pub fn operand(&self, operand: &Operand) -> String {
+        match (self.dialect, *operand) {
+            (Dialect::Intel, Operand::Reg(register)) => self.case(&register.to_string()),
+            (Dialect::Intel, Operand::Pair(pair)) => self.case(&pair.to_string()),
+            (Dialect::Zilog, Operand::Reg(Register::M)) => self.case("(HL)"),
+            (Dialect::Zilog, Operand::Reg(register)) => self.case(&register.to_string()),
+            (Dialect::Zilog, Operand::Pair(pair)) => self.case(match pair {
+                RegisterPair::B => "BC",
+                RegisterPair::D => "DE",
+                RegisterPair::H => "HL",
+                RegisterPair::SP => "SP",
+                RegisterPair::PSW => "AF",
+            }),
+            (_, Operand::Byte(value)) | (_, Operand::Port(value)) => self.byte(value),
+            (_, Operand::Word(value)) | (_, Operand::Address(value)) => self.word(value),
+            // RST 7 in Intel's, RST 38H in Zilog's.
+            (Dialect::Intel, Operand::Vector(number)) => number.to_string(),
+            (Dialect::Zilog, Operand::Vector(number)) => self.byte(number * 8),
+        }
+    }
+
+    // The mnemonic and the operands, with label names in place of the
+    // addresses they stand for.
+    pub fn instruction_parts(
+        &self,
+        instruction: &Instruction,
+        labels: Option<&Labels>,
+    ) -> (String, Vec<String>) {
+        let operands: Vec<String> = instruction
+            .operands
+            .iter()
+            .map(
+                |operand| match labels.and_then(|labels| instruction.label_for(operand, labels)) {
+                    Some(name) => name.to_string(),
+                    None => self.operand(operand),
+                },
+            )
+            .collect();
+        match self.dialect {
+            Dialect::Intel => (self.case(&instruction.mnemonic.to_string()), operands),
+            Dialect::Zilog => self.zilog(instruction.mnemonic, operands),
+        }
+    }
+
+    // e.g. `MVI B,#5`
+    pub fn instruction(&self, instruction: &Instruction, labels: Option<&Labels>) -> String {
+        let (mnemonic, operands) = self.instruction_parts(instruction, labels);
+        if operands.is_empty() {
+            mnemonic
+        } else {
+            format!("{} {}", mnemonic, operands.join(","))
+        }
+    }
+
+    // The Z80 way of writing an 8080 instruction, from its operands already
+    // written out.
+    fn zilog(&self, mnemonic: Mnemonic, operands: Vec<String>) -> (String, Vec<String>) {
+        let a = self.case("A");
+        let hl = self.case("HL");
+        let sp = self.case("SP");
+        let at = |operand: &String| format!("({})", operand);
+        let mut operands = operands.into_iter();
+        let mut next = || operands.next().unwrap_or_default();
+        if let Some((name, condition)) = condition(mnemonic) {
+            let mut operands = vec![self.case(condition)];
+            if name != "RET" {
+                operands.push(next());
+            }
+            return (self.case(name), operands);
+        }
+        let (name, operands) = match mnemonic {
+            Mnemonic::Mov | Mnemonic::Mvi | Mnemonic::Lxi => ("LD", vec![next(), next()]),
+            Mnemonic::Lda | Mnemonic::Ldax => ("LD", vec![a, at(&next())]),
+            Mnemonic::Sta | Mnemonic::Stax => ("LD", vec![at(&next()), a]),
+            Mnemonic::Lhld => ("LD", vec![hl, at(&next())]),
+            Mnemonic::Shld => ("LD", vec![at(&next()), hl]),
+            Mnemonic::Sphl => ("LD", vec![sp, hl]),
+            Mnemonic::Xchg => ("EX", vec![self.case("DE"), hl]),
+            Mnemonic::Xthl => ("EX", vec![at(&sp), hl]),
+            Mnemonic::Pchl => ("JP", vec![at(&hl)]),
+            Mnemonic::Inr | Mnemonic::Inx => ("INC", vec![next()]),
+            Mnemonic::Dcr | Mnemonic::Dcx => ("DEC", vec![next()]),
+            Mnemonic::Dad => ("ADD", vec![hl, next()]),
+            Mnemonic::Add | Mnemonic::Adi => ("ADD", vec![a, next()]),
+            Mnemonic::Adc | Mnemonic::Aci => ("ADC", vec![a, next()]),
+            Mnemonic::Sbb | Mnemonic::Sbi => ("SBC", vec![a, next()]),
+            Mnemonic::Sub | Mnemonic::Sui => ("SUB", vec![next()]),
+            Mnemonic::Ana | Mnemonic::Ani => ("AND", vec![next()]),
+            Mnemonic::Xra | Mnemonic::Xri => ("XOR", vec![next()]),
+            Mnemonic::Ora | Mnemonic::Ori => ("OR", vec![next()]),
+            Mnemonic::Cmp | Mnemonic::Cpi => ("CP", vec![next()]),
+            Mnemonic::In => ("IN", vec![a, at(&next())]),
+            Mnemonic::Out => ("OUT", vec![at(&next()), a]),
+            Mnemonic::Jmp => ("JP", vec![next()]),
+            Mnemonic::Rlc => ("RLCA", vec![]),
+            Mnemonic::Rrc => ("RRCA", vec![]),
+            Mnemonic::Ral => ("RLA", vec![]),
+            Mnemonic::Rar => ("RRA", vec![]),
+            Mnemonic::Cma => ("CPL", vec![]),
+            Mnemonic::Stc => ("SCF", vec![]),
+            Mnemonic::Cmc => ("CCF", vec![]),
+            Mnemonic::Hlt => ("HALT", vec![]),
+            // NOP, DAA, PUSH, POP, CALL, RET, RST, DI and EI are the same in both.
+            _ => return (self.case(&mnemonic.to_string()), operands.collect()),
+        };
+        (self.case(name), operands)
+    }
+
+    // The directive and the items for a line of data. Pointers get label
+    // names where there are any.
+    pub fn data_parts(
+        &self,
+        bytes: &[u8],
+        format: DataFormat,
+        labels: Option<&Labels>,
+    ) -> (String, Vec<String>) {
+        match format {
+            DataFormat::Bytes => (
+                self.directive("DB"),
+                bytes.iter().map(|&byte| self.byte(byte)).collect(),
+            ),
+            DataFormat::Words | DataFormat::Pointers => (
+                self.directive("DW"),
+                listing::words(bytes)
+                    .into_iter()
+                    .map(|word| match labels.and_then(|labels| labels.get(word)) {
+                        Some(name) if format == DataFormat::Pointers => name.to_string(),
+                        _ => self.word(word),
+                    })
+                    .collect(),
+            ),
+            DataFormat::Text => (
+                match self.dialect {
+                    Dialect::Intel => self.directive("DB"),
+                    Dialect::Zilog => self.case("DEFM"),
+                },
+                listing::text_pieces(bytes)
+                    .into_iter()
+                    .map(|piece| match piece {
+                        TextPiece::Text(text) => format!("\"{}\"", text),
+                        TextPiece::Byte(byte) => self.byte(byte),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // e.g. `DB #f,#b`
+    pub fn data(&self, bytes: &[u8], format: DataFormat, labels: Option<&Labels>) -> String {
+        let (directive, items) = self.data_parts(bytes, format, labels);
+        format!("{} {}", directive, items.join(","))
+    }
+}
+
+// Conditional jumps, calls and returns, which Zilog writes as JP, CALL or
+// RET with the condition as an operand.
+fn condition(mnemonic: Mnemonic) -> Option<(&'static str, &'static str)> {
+    let name = mnemonic.to_string();
+    let (first, rest) = name.split_at(1);
+    let instruction = match first {
+        "J" => "JP",
+        "C" => "CALL",
+        "R" => "RET",
+        _ => return None,
+    };
+    let condition = match rest {
+        "NZ" => "NZ",
+        "Z" => "Z",
+        "NC" => "NC",
+        "C" => "C",
+        "PO" => "PO",
+        "PE" => "PE",
+        "P" => "P",
+        "M" => "M",
+        _ => return None,
+    };
+    Some((instruction, condition))
+}
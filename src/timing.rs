@@ -0,0 +1,63 @@
+// How long each instruction takes, in T-states (clock cycles). A 2MHz 8080
+// runs 2,000,000 of these a second, which is what delay loops and video
+// timing get counted in.
+
+use instruction::Instruction;
+
+// Not taken counts for the conditional calls and returns, see TAKEN_EXTRA.
+const CYCLES: [u8; 256] = [
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, // 0x00
+    4, 10, 7, 5, 5, 5, 7, 4, 4, 10, 7, 5, 5, 5, 7, 4, // 0x10
+    4, 10, 16, 5, 5, 5, 7, 4, 4, 10, 16, 5, 5, 5, 7, 4, // 0x20
+    4, 10, 13, 5, 10, 10, 10, 4, 4, 10, 13, 5, 5, 5, 7, 4, // 0x30
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x40
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x50
+    5, 5, 5, 5, 5, 5, 7, 5, 5, 5, 5, 5, 5, 5, 7, 5, // 0x60
+    7, 7, 7, 7, 7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 7, 5, // 0x70
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0x80
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0x90
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0xa0
+    4, 4, 4, 4, 4, 4, 7, 4, 4, 4, 4, 4, 4, 4, 7, 4, // 0xb0
+    5, 10, 10, 10, 11, 11, 7, 11, 5, 10, 10, 10, 11, 17, 7, 11, // 0xc0
+    5, 10, 10, 10, 11, 11, 7, 11, 5, 10, 10, 10, 11, 17, 7, 11, // 0xd0
+    5, 10, 10, 18, 11, 11, 7, 11, 5, 5, 10, 4, 11, 17, 7, 11, // 0xe0
+    5, 10, 10, 4, 11, 11, 7, 11, 5, 5, 10, 4, 11, 17, 7, 11, // 0xf0
+];
+
+// A conditional CALL or RET that's taken has to push or pop the return address.
+const TAKEN_EXTRA: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub cycles: u8,
+    // What a conditional call or return takes when the condition holds.
+    // Conditional jumps take the same either way so they don't have one.
+    pub taken: Option<u8>,
+}
+
+impl Timing {
+    // The most it can take, for working out worst cases.
+    pub fn most(&self) -> u8 {
+        self.taken.unwrap_or(self.cycles)
+    }
+}
+
+pub fn timing(opcode: u8) -> Timing {
+    let cycles = CYCLES[opcode as usize];
+    // Ccc is 11 000 100 and Rcc is 11 000 000, with the condition in the middle.
+    let conditional = opcode & 0xc7 == 0xc4 || opcode & 0xc7 == 0xc0;
+    Timing {
+        cycles,
+        taken: if conditional {
+            Some(cycles + TAKEN_EXTRA)
+        } else {
+            None
+        },
+    }
+}
+
+impl Instruction {
+    pub fn timing(&self) -> Timing {
+        timing(self.opcode)
+    }
+}
@@ -0,0 +1,146 @@
+// Recursive descent: rather than decoding every byte in order, start from the
+// entry points and only decode what the code can actually reach. Anything
+// that's never reached is left alone so data tables can't knock the
+// instructions after them out of step.
+
+use decoder::decode;
+use dispatch::{self, Target};
+use image::Image;
+use instruction::{Flow, Instruction};
+use std::collections::BTreeMap;
+
+// How far back from a PCHL to look for where H and L were set.
+const DISPATCH_WINDOW: usize = 16;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traced {
+    pub instructions: BTreeMap<u16, Instruction>,
+    // Jump tables found behind a PCHL, with the addresses in each.
+    pub tables: BTreeMap<u16, Vec<u16>>,
+}
+
+impl Traced {
+    // The first and last byte of each jump table. A table can't go past the
+    // top of memory, but one that ends right at 0xffff is fine.
+    pub fn table_ranges(&self) -> Vec<(u16, u16)> {
+        self.tables
+            .iter()
+            .filter(|&(_, entries)| !entries.is_empty())
+            .map(|(&start, entries)| {
+                let end = usize::from(start) + entries.len() * 2 - 1;
+                (start, end.min(0xffff) as u16)
+            })
+            .collect()
+    }
+}
+
+// Where the 8080 starts after reset, plus the eight RST vectors that
+// interrupts can jump to. Code loaded somewhere else (a CP/M .COM file at
+// 0x0100, say) starts where it's loaded instead, so that goes first.
+pub fn default_entry_points(image: &Image) -> Vec<u16> {
+    let origin = image.regions().first().map_or(0, |region| region.start);
+    let mut entry_points = vec![origin];
+    for vector in (0..8).map(|number| number * 8) {
+        if vector > origin && image.contains(vector) {
+            entry_points.push(vector);
+        }
+    }
+    entry_points
+}
+
+// Follows the control flow through the image from every entry point,
+// returning each reachable instruction keyed by its address.
+pub fn trace(image: &Image, entry_points: &[u16]) -> BTreeMap<u16, Instruction> {
+    follow(image, entry_points).instructions
+}
+
+// The same as `trace`, also keeping the jump tables it found on the way.
+// Wherever a PCHL goes (see `dispatch`) is followed too, and a table's bytes
+// are kept out of the code.
+pub fn follow(image: &Image, entry_points: &[u16]) -> Traced {
+    let mut instructions = BTreeMap::new();
+    let mut tables = BTreeMap::new();
+    // Which addresses already belong to a decoded instruction.
+    let mut covered = vec![false; 0x10000];
+    let mut pending: Vec<u16> = entry_points.iter().rev().cloned().collect();
+    while let Some(address) = pending.pop() {
+        let position = address as usize;
+        if covered[position] {
+            continue;
+        }
+        let instruction = match image
+            .bytes_from(address)
+            .map(|bytes| decode(bytes, address))
+        {
+            Some(Ok(instruction)) => instruction,
+            // Outside anything that was loaded, or runs off the end of it, so
+            // this path can't go any further.
+            _ => continue,
+        };
+        let end = position + instruction.length as usize;
+        if covered[position..end].iter().any(|&byte| byte) {
+            // The operand bytes overlap an instruction we already found, which
+            // means one of the two paths is really reading data. Trust the first.
+            continue;
+        }
+        for byte in &mut covered[position..end] {
+            *byte = true;
+        }
+        match instruction.flow() {
+            Flow::Next | Flow::ConditionalReturn => pending.push(instruction.next_address()),
+            Flow::Jump(target) => pending.push(target),
+            Flow::ConditionalJump(target) | Flow::Call(target) | Flow::ConditionalCall(target) => {
+                pending.push(target);
+                pending.push(instruction.next_address());
+            }
+            Flow::Indirect => {
+                let before = leading_up_to(&instructions, address);
+                match dispatch::resolve(&before) {
+                    Some(Target::Address(target)) => pending.push(target),
+                    Some(Target::Table(table)) if !tables.contains_key(&table) => {
+                        let entries =
+                            dispatch::entries(image, table, |address| covered[address as usize]);
+                        let end = table as usize + entries.len() * 2;
+                        for byte in &mut covered[table as usize..end] {
+                            *byte = true;
+                        }
+                        pending.extend(entries.iter().rev());
+                        if !entries.is_empty() {
+                            tables.insert(table, entries);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Nowhere we can know about statically.
+            Flow::Return => {}
+        }
+        instructions.insert(address, instruction);
+    }
+    Traced {
+        instructions,
+        tables,
+    }
+}
+
+// The instructions that run straight into `address`, oldest first, going
+// back until one that jumps, calls or returns.
+fn leading_up_to(instructions: &BTreeMap<u16, Instruction>, address: u16) -> Vec<Instruction> {
+    let mut before = Vec::new();
+    let mut next = address;
+    while before.len() < DISPATCH_WINDOW {
+        let previous = instructions
+            .range(next.saturating_sub(3)..next)
+            .map(|(_, instruction)| instruction)
+            .find(|instruction| instruction.next_address() == next);
+        match previous {
+            Some(instruction) if instruction.flow() == Flow::Next => {
+                before.push(instruction.clone());
+                next = instruction.address;
+            }
+            _ => break,
+        }
+    }
+    before.reverse();
+    before
+}
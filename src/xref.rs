@@ -0,0 +1,158 @@
+// Cross references: for every address the code mentions, which instructions
+// read it, write it, jump to it or call it. Patching a routine means finding
+// every caller, and this saves grepping the listing for its address.
+
+use instruction::{Flow, Instruction, Mnemonic, Operand, Register, RegisterPair};
+use listing::Line;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Access {
+    Call,
+    Jump,
+    Read,
+    Write,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match *self {
+            Access::Read => "read by",
+            Access::Write => "written by",
+            Access::Jump => "jumped to from",
+            Access::Call => "called from",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// One instruction that uses an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reference {
+    pub access: Access,
+    // The address of the instruction doing it.
+    pub from: u16,
+}
+
+// Every address something refers to, with what refers to it, in order.
+//
+// LDAX and STAX only say which address they use when the pair was just set
+// with LXI, so the value of B and D is kept track of until something changes
+// it or the code could have come from somewhere else. PCHL straight after
+// LXI H counts as a jump, the same as it does for labels.
+pub fn references(lines: &[Line]) -> BTreeMap<u16, Vec<Reference>> {
+    let targets: HashSet<u16> = lines
+        .iter()
+        .filter_map(|line| match *line {
+            Line::Instruction(ref instruction) => instruction.target(),
+            _ => None,
+        })
+        .collect();
+    let mut references: BTreeMap<u16, Vec<Reference>> = BTreeMap::new();
+    // What LXI last put in B, D and H.
+    let mut pairs: HashMap<RegisterPair, u16> = HashMap::new();
+    for line in lines {
+        let instruction = match *line {
+            Line::Instruction(ref instruction) => instruction,
+            Line::Data { .. } => {
+                pairs.clear();
+                continue;
+            }
+        };
+        if targets.contains(&instruction.address) {
+            pairs.clear();
+        }
+        let mut add = |address: u16, access: Access| {
+            references.entry(address).or_default().push(Reference {
+                access,
+                from: instruction.address,
+            })
+        };
+        match (instruction.mnemonic, &instruction.operands[..]) {
+            (Mnemonic::Lda, &[Operand::Address(address)])
+            | (Mnemonic::Lhld, &[Operand::Address(address)]) => add(address, Access::Read),
+            (Mnemonic::Sta, &[Operand::Address(address)])
+            | (Mnemonic::Shld, &[Operand::Address(address)]) => add(address, Access::Write),
+            (Mnemonic::Ldax, &[Operand::Pair(pair)]) => {
+                if let Some(&address) = pairs.get(&pair) {
+                    add(address, Access::Read);
+                }
+            }
+            (Mnemonic::Stax, &[Operand::Pair(pair)]) => {
+                if let Some(&address) = pairs.get(&pair) {
+                    add(address, Access::Write);
+                }
+            }
+            _ => {}
+        }
+        match instruction.flow() {
+            Flow::Jump(target) | Flow::ConditionalJump(target) => add(target, Access::Jump),
+            Flow::Call(target) | Flow::ConditionalCall(target) => add(target, Access::Call),
+            Flow::Indirect => {
+                if let Some(&address) = pairs.get(&RegisterPair::H) {
+                    add(address, Access::Jump);
+                }
+            }
+            _ => {}
+        }
+        // A call can change anything, and after a jump or return the next
+        // line is only reached from somewhere else.
+        if instruction.flow() != Flow::Next {
+            pairs.clear();
+            continue;
+        }
+        pairs.retain(|&pair, _| !changes(instruction, pair));
+        if let (Mnemonic::Lxi, &[Operand::Pair(pair), Operand::Word(value)]) =
+            (instruction.mnemonic, &instruction.operands[..])
+        {
+            pairs.insert(pair, value);
+        }
+    }
+    for list in references.values_mut() {
+        list.sort();
+        list.dedup();
+    }
+    references
+}
+
+// Whether the instruction changes either half of the pair.
+fn changes(instruction: &Instruction, pair: RegisterPair) -> bool {
+    let (high, low) = match pair {
+        RegisterPair::B => (Register::B, Register::C),
+        RegisterPair::D => (Register::D, Register::E),
+        RegisterPair::H => (Register::H, Register::L),
+        _ => return true,
+    };
+    match (instruction.mnemonic, instruction.operands.first()) {
+        (Mnemonic::Mov, Some(&Operand::Reg(register)))
+        | (Mnemonic::Mvi, Some(&Operand::Reg(register)))
+        | (Mnemonic::Inr, Some(&Operand::Reg(register)))
+        | (Mnemonic::Dcr, Some(&Operand::Reg(register))) => register == high || register == low,
+        (Mnemonic::Lxi, Some(&Operand::Pair(other)))
+        | (Mnemonic::Inx, Some(&Operand::Pair(other)))
+        | (Mnemonic::Dcx, Some(&Operand::Pair(other)))
+        | (Mnemonic::Pop, Some(&Operand::Pair(other))) => other == pair,
+        (Mnemonic::Xchg, _) => pair != RegisterPair::B,
+        (Mnemonic::Dad, _) | (Mnemonic::Lhld, _) | (Mnemonic::Xthl, _) => pair == RegisterPair::H,
+        _ => false,
+    }
+}
+
+// The references grouped by what they do, e.g. `called from 105, 1a0;
+// jumped to from 120`.
+pub fn describe(references: &[Reference]) -> String {
+    let mut groups: Vec<(Access, Vec<String>)> = Vec::new();
+    for reference in references {
+        let from = format!("{:x}", reference.from);
+        match groups.last_mut() {
+            Some(&mut (access, ref mut list)) if access == reference.access => list.push(from),
+            _ => groups.push((reference.access, vec![from])),
+        }
+    }
+    groups
+        .iter()
+        .map(|&(access, ref list)| format!("{} {}", access, list.join(", ")))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
@@ -0,0 +1,142 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::assemble;
+
+fn bytes(source: &str) -> Vec<u8> {
+    assemble(source).unwrap().bytes
+}
+
+#[test]
+fn keeps_the_case_of_characters() {
+    assert_eq!(bytes("MVI A,'a'\nCPI 'z'"), vec![0x3e, 0x61, 0xfe, 0x7a]);
+    assert_eq!(bytes("mvi a,'A'\ncpi 'a'+1"), vec![0x3e, 0x41, 0xfe, 0x62]);
+    assert_eq!(bytes("DB 'a','B'"), vec![0x61, 0x42]);
+    // Names are still the same whatever case they're written in.
+    assert_eq!(
+        bytes("start: lxi h,START\njmp Start"),
+        vec![0x21, 0, 0, 0xc3, 0, 0]
+    );
+}
+
+#[test]
+fn reports_overflow_with_the_line() {
+    let error = assemble("NOP\nDW 7FFFFFFFFFFFFFFFH+1").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.message, "Expression is too big");
+    for expression in &[
+        "4000000000000000H*4",
+        "-(-7FFFFFFFFFFFFFFFH-1)",
+        "(-7FFFFFFFFFFFFFFFH-1)/-1",
+        "-7FFFFFFFFFFFFFFFH-2",
+    ] {
+        let error = assemble(&format!("DW {}", expression)).unwrap_err();
+        assert_eq!(error.message, "Expression is too big", "{}", expression);
+    }
+}
+
+#[test]
+fn org_and_ds_need_names_defined_first() {
+    let error = assemble("ORG START\nNOP\nSTART EQU 100H").unwrap_err();
+    assert_eq!(error.line, 1);
+    assert_eq!(
+        error.message,
+        "ORG can only use names defined above it, and START isn't"
+    );
+    let error = assemble("NOP\nDS SIZE\nSIZE EQU 4").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(
+        error.message,
+        "DS can only use names defined above it, and SIZE isn't"
+    );
+    let assembly = assemble("SIZE EQU 2\nORG 100H\nDS SIZE\nNOP").unwrap();
+    assert_eq!((assembly.origin, assembly.bytes), (0x102, vec![0]));
+}
+
+#[test]
+fn encodes_instructions() {
+    assert_eq!(
+        bytes(
+            "
+        ORG 0
+START:  LXI SP,2400H
+        MVI M,1CH
+        MOV A,M
+        ADD B
+        CPI ':'
+        JNZ START
+        CALL NEXT
+        RST 7
+NEXT:   PUSH PSW
+        IN 1
+        OUT 3
+        RET
+"
+        ),
+        vec![
+            0x31, 0x00, 0x24, 0x36, 0x1c, 0x7e, 0x80, 0xfe, 0x3a, 0xc2, 0x00, 0x00, 0xcd, 0x10,
+            0x00, 0xff, 0xf5, 0xdb, 0x01, 0xd3, 0x03, 0xc9,
+        ]
+    );
+}
+
+#[test]
+fn reads_numbers_and_expressions() {
+    assert_eq!(
+        bytes("DB 10, 0AH, 0x0a, $0a, 1010B, 12Q, 12O, 10D"),
+        vec![10; 8]
+    );
+    assert_eq!(
+        bytes("DB 2+3*4, (2+3)*4, 17/5, 17%5, 0F0H&3CH, 0F0H|0FH, 0FFH^0FH, ~0"),
+        vec![14, 20, 3, 2, 0x30, 0xff, 0xf0, 0xff]
+    );
+    assert_eq!(
+        bytes("DB HIGH 1234H, LOW 1234H, 'A'+80H, -1"),
+        vec![0x12, 0x34, 0xc1, 0xff]
+    );
+    // $ is wherever the line starts.
+    assert_eq!(bytes("ORG 100H\nDW $, $+2"), vec![0x00, 0x01, 0x02, 0x01]);
+}
+
+#[test]
+fn handles_directives() {
+    let assembly = assemble(
+        "
+SIZE    EQU 2
+        ORG 100H
+        JMP LATER       ; a name used before it's defined
+        DS SIZE
+LATER:  DB 'HI', 0
+        DW LATER
+        END
+        this isn't read
+",
+    )
+    .unwrap();
+    assert_eq!(assembly.origin, 0x100);
+    assert_eq!(
+        assembly.bytes,
+        vec![0xc3, 0x05, 0x01, 0, 0, b'H', b'I', 0, 0x05, 0x01]
+    );
+    // Zilog's names for the data directives work too.
+    assert_eq!(
+        bytes("DEFB 1\nDEFW 2\nDEFM \"AB\""),
+        vec![1, 2, 0, b'A', b'B']
+    );
+}
+
+#[test]
+fn reports_mistakes_with_their_line() {
+    let error = |source: &str| assemble(source).unwrap_err().to_string();
+    assert_eq!(
+        error("NOP\n    FROB A"),
+        "line 2: Unknown instruction: FROB"
+    );
+    assert_eq!(error("MOV A"), "line 1: Bad operands for MOV: A");
+    assert_eq!(error("MVI A,256"), "line 1: 256 doesn't fit in a byte");
+    assert_eq!(error("JMP NOWHERE"), "line 1: Undefined symbol: NOWHERE");
+    assert_eq!(
+        error("HERE: NOP\nHERE: NOP"),
+        "line 2: HERE is defined more than once"
+    );
+    assert_eq!(error("EQU 5"), "line 1: EQU needs a name");
+}
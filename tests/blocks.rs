@@ -0,0 +1,87 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::blocks::{self, Edge};
+use eightyeighty_disassembler::{labels, Line, Syntax};
+
+const PROGRAM: &[u8] = &[
+    0x06, 0x0a, // 0000: START: MVI B,10
+    0xcd, 0x0d, 0x00, // 0002: LOOP: CALL WAIT
+    0x05, // 0005: DCR B
+    0xc2, 0x02, 0x00, // 0006: JNZ LOOP
+    0xc8, // 0009: RZ
+    0xc3, 0x00, 0x00, // 000a: JMP START
+    0x21, 0x11, 0x00, // 000d: WAIT: LXI H,DONE
+    0xe9, // 0010: PCHL
+    0xc9, // 0011: DONE: RET
+];
+
+fn lines() -> Vec<Line> {
+    common::lines_from_bytes(PROGRAM, 0)
+}
+
+#[test]
+fn splits_code_into_blocks() {
+    let blocks = blocks::blocks(&lines());
+    let found: Vec<_> = blocks
+        .values()
+        .map(|block| (block.start, block.end(), block.successors.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0x00, 0x02, vec![(0x02, Edge::Next)]),
+            (0x02, 0x05, vec![(0x05, Edge::Next)]),
+            (0x05, 0x09, vec![(0x02, Edge::Branch), (0x09, Edge::Next)]),
+            (0x09, 0x0a, vec![(0x0a, Edge::Next)]),
+            (0x0a, 0x0d, vec![(0x00, Edge::Jump)]),
+            (0x0d, 0x11, vec![(0x11, Edge::Jump)]),
+            (0x11, 0x12, vec![]),
+        ]
+    );
+    assert_eq!(blocks[&0x02].call, Some(0x0d));
+
+    // Calls aren't followed, so WAIT isn't part of the main loop.
+    let routine: Vec<u16> = blocks::routine(&blocks, 0)
+        .iter()
+        .map(|block| block.start)
+        .collect();
+    assert_eq!(routine, vec![0x00, 0x02, 0x05, 0x09, 0x0a]);
+}
+
+#[test]
+fn writes_a_graphviz_graph() {
+    let lines = lines();
+    let labels = labels::generate(&lines);
+    let mut dot = Vec::new();
+    blocks::write_dot(
+        &mut dot,
+        &blocks::blocks(&lines),
+        &[0, 0x0d],
+        &labels,
+        &Syntax::default(),
+    )
+    .unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph cfg {\n"), "{}", dot);
+    assert!(
+        dot.contains("    subgraph \"cluster_000d\" {\n        label=\"SUB_000D\";\n"),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains(
+            "\"000d_000d\" [label=\"SUB_000D:\\ld     21: LXI H,L_0011\\l10     e9: PCHL\\l\"];"
+        ),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains("\"0000_0005\" -> \"0000_0002\" [label=\"taken\"];"),
+        "{}",
+        dot
+    );
+    assert!(dot.contains("\"0000_000a\" -> \"0000_0000\";"), "{}", dot);
+    assert!(dot.ends_with("    }\n}\n"), "{}", dot);
+}
@@ -0,0 +1,110 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::calls::{self, CallKind, Reach};
+use eightyeighty_disassembler::labels;
+
+const PROGRAM: &str = "
+        ORG 100H
+START:  CALL ONE
+        CALL DONE
+        LXI H,HANDLER
+        PCHL
+ONE:    CALL TWO
+        RET
+TWO:    CZ ONE
+        JMP DONE
+DONE:   RET
+HANDLER: CALL DONE
+        RET
+        CALL LOST
+        RET
+LOST:   RET
+";
+
+fn graph() -> (calls::CallGraph, labels::Labels) {
+    let lines = common::lines(PROGRAM);
+    (
+        calls::call_graph(&lines, &[0x100]),
+        labels::generate(&lines),
+    )
+}
+
+#[test]
+fn finds_routines_and_what_they_call() {
+    let (graph, _) = graph();
+    let routines: Vec<_> = graph
+        .routines
+        .values()
+        .map(|routine| (routine.entry, routine.reach, routine.calls.clone()))
+        .collect();
+    assert_eq!(
+        routines,
+        vec![
+            (
+                0x100,
+                Reach::Entry,
+                vec![
+                    (0x10a, CallKind::Call),
+                    (0x114, CallKind::Call),
+                    (0x115, CallKind::Indirect)
+                ]
+            ),
+            (0x10a, Reach::Call, vec![(0x10e, CallKind::Call)]),
+            (
+                0x10e,
+                Reach::Call,
+                vec![(0x10a, CallKind::Call), (0x114, CallKind::Tail)]
+            ),
+            (0x114, Reach::Call, vec![]),
+            (0x115, Reach::Indirect, vec![(0x114, CallKind::Call)]),
+            (0x11d, Reach::Call, vec![]),
+        ]
+    );
+    // The tail jump to DONE isn't part of TWO.
+    assert_eq!(graph.routines[&0x10e].blocks, vec![0x10e, 0x111]);
+
+    assert_eq!(graph.recursion(), vec![vec![0x10a, 0x10e]]);
+    assert_eq!(graph.unreachable(), vec![0x11d]);
+    assert_eq!(graph.indirect(), vec![0x115]);
+}
+
+#[test]
+fn writes_a_tree_and_a_graph() {
+    let (graph, labels) = graph();
+    let mut tree = Vec::new();
+    graph.write_tree(&mut tree, &labels).unwrap();
+    assert_eq!(
+        String::from_utf8(tree).unwrap(),
+        "0100
+  SUB_010A
+    SUB_010E
+      SUB_010A (recursive)
+      SUB_0114 (tail)
+  SUB_0114 ...
+  L_0115 (PCHL)
+    SUB_0114 ...
+SUB_011D
+
+Recursive: SUB_010A -> SUB_010E -> SUB_010A
+Unreachable: SUB_011D
+Only reached through PCHL: L_0115
+"
+    );
+
+    let mut dot = Vec::new();
+    graph.write_dot(&mut dot, &labels).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("    \"0100\" [style=bold];\n"), "{}", dot);
+    assert!(
+        dot.contains("    \"SUB_010E\" -> \"SUB_0114\" [style=dashed];\n"),
+        "{}",
+        dot
+    );
+    assert!(
+        dot.contains("    \"0100\" -> \"L_0115\" [style=dotted];\n"),
+        "{}",
+        dot
+    );
+}
@@ -0,0 +1,71 @@
+// Fixtures shared by the tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use eightyeighty_disassembler::{assemble, listing, Image, Line};
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+
+// What `source` assembles to, loaded where its ORG says.
+pub fn image(source: &str) -> Image {
+    let program = assemble(source).unwrap();
+    Image::from_binary(&program.bytes, program.origin).unwrap()
+}
+
+// The listing of `source`, decoded straight through.
+pub fn lines(source: &str) -> Vec<Line> {
+    listing::linear(&image(source))
+}
+
+// The listing of bytes written out by hand, loaded at `origin`, for tests
+// that shouldn't depend on the assembler getting things right.
+pub fn lines_from_bytes(bytes: &[u8], origin: u16) -> Vec<Line> {
+    listing::linear(&Image::from_binary(bytes, origin).unwrap())
+}
+
+// Collects what a CP/M program prints so it can be checked.
+#[derive(Clone, Default)]
+pub struct Console(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Console {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Console {
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+// A directory for one test's files, like a CP/M program's drive, removed
+// when the test ends whether it passed or not.
+pub struct Scratch(PathBuf);
+
+impl Scratch {
+    pub fn new(name: &str) -> Scratch {
+        let directory = env::temp_dir().join(format!("cpm-{}-{}", name, process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        Scratch(directory)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
@@ -0,0 +1,294 @@
+// Runs small CP/M programs, put together with the built in assembler,
+// against a scratch directory.
+
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use common::{Console, Scratch};
+use eightyeighty_disassembler::{assemble, cpm, listing, Cpm, Image};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::thread;
+
+// Input from a terminal nobody is typing at.
+struct Idle;
+
+impl Read for Idle {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        loop {
+            thread::park();
+        }
+    }
+}
+
+// Assembles and runs a program, handing back what it printed.
+fn run(source: &str, arguments: &str, input: &'static [u8], directory: &Path) -> String {
+    run_with_input(source, arguments, Box::new(input), directory)
+}
+
+fn run_with_input(
+    source: &str,
+    arguments: &str,
+    input: Box<dyn Read + Send>,
+    directory: &Path,
+) -> String {
+    let program = assemble(source).unwrap();
+    assert_eq!(program.origin, 0x0100);
+    let mut machine = Cpm::new(&program.bytes, arguments, directory.to_path_buf());
+    let console = Console::default();
+    machine.set_console(input, Box::new(console.clone()));
+    for _ in 0..100_000 {
+        if machine.finished() {
+            break;
+        }
+        machine.step();
+    }
+    assert!(machine.finished(), "Program didn't warm boot");
+    console.text()
+}
+
+#[test]
+fn console() {
+    let source = "
+        ORG 100H
+        LXI D,HELLO
+        MVI C,9
+        CALL 5
+        MVI C,1
+        CALL 5
+        MOV E,A
+        INR E
+        MVI C,2
+        CALL 5
+        JMP 0
+HELLO:  DB 'Hello, world', 13, 10, '$'
+";
+    let output = run(source, "", b"x", Scratch::new("console").path());
+    assert_eq!(output, "Hello, world\nxy");
+}
+
+#[test]
+fn command_line() {
+    // Prints the name the CCP put in the default FCB.
+    let source = "
+        ORG 100H
+        MVI A,'$'
+        STA 5CH+12
+        LXI D,5CH+1
+        MVI C,9
+        CALL 5
+        RET
+";
+    let output = run(source, "foo.txt", b"", Scratch::new("command-line").path());
+    assert_eq!(output, "FOO     TXT");
+}
+
+#[test]
+fn files() {
+    let scratch = Scratch::new("files");
+    let directory = scratch.path();
+    fs::write(directory.join("IN.TXT"), "Copied$").unwrap();
+    // Copies IN.TXT to OUT.TXT a record at a time, then prints OUT.TXT.
+    let source = "
+        ORG 100H
+        LXI D,INPUT
+        MVI C,15
+        CALL 5
+        LXI D,OUTPUT
+        MVI C,22
+        CALL 5
+COPY:   LXI D,INPUT
+        MVI C,20
+        CALL 5
+        ORA A
+        JNZ DONE
+        LXI D,OUTPUT
+        MVI C,21
+        CALL 5
+        JMP COPY
+DONE:   LXI D,OUTPUT
+        MVI C,16
+        CALL 5
+        LXI D,OUTPUT
+        MVI C,15
+        CALL 5
+        LXI D,OUTPUT
+        MVI C,20
+        CALL 5
+        LXI D,80H
+        MVI C,9
+        CALL 5
+        JMP 0
+INPUT:  DB 0, 'IN      TXT'
+        DS 24
+OUTPUT: DB 0, 'OUT     TXT'
+        DS 24
+";
+    let output = run(source, "", b"", directory);
+    assert_eq!(output, "Copied");
+    let copy = fs::read(directory.join("OUT.TXT")).unwrap();
+    assert_eq!(copy.len(), 128);
+    assert!(copy.starts_with(b"Copied$\x1a"));
+}
+
+#[test]
+fn bdos_calls_are_named() {
+    let program = assemble(
+        "
+        ORG 100H
+        MVI C,9
+        LXI D,0
+        CALL 5
+        CALL 5
+        MVI C,200
+        JMP 5
+",
+    )
+    .unwrap();
+    let image = Image::from_binary(&program.bytes, program.origin).unwrap();
+    let calls = cpm::bdos_calls(&listing::linear(&image));
+    let calls: Vec<(u16, &str)> = calls
+        .iter()
+        .map(|(&address, comment)| (address, &comment[..]))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            (0x0105, "BDOS 9: print string"),
+            (0x0108, "BDOS"),
+            (0x010d, "BDOS 200"),
+        ]
+    );
+}
+
+#[test]
+fn file_names_stay_in_the_directory() {
+    let scratch = Scratch::new("names");
+    let directory = scratch.path();
+    fs::write(directory.join("IN.TXT"), "").unwrap();
+    // Makes ../ESCAPE and renames IN.TXT to ../../X, printing what each
+    // call returned.
+    let source = "
+        ORG 100H
+        LXI D,ESCAPE
+        MVI C,22
+        CALL 5
+        MOV E,A
+        MVI C,2
+        CALL 5
+        LXI D,RENAME
+        MVI C,23
+        CALL 5
+        MOV E,A
+        MVI C,2
+        CALL 5
+        JMP 0
+ESCAPE: DB 0, '../ESCA    '
+        DS 24
+RENAME: DB 0, 'IN      TXT', 0, 0, 0, 0
+        DB 0, '../../X    '
+        DS 8
+";
+    let output = run(source, "", b"", directory);
+    assert_eq!(output.as_bytes(), [0x7f, 0x7f]);
+    assert!(directory.join("IN.TXT").exists());
+    assert!(!directory.parent().unwrap().join("ESCA").exists());
+}
+
+#[test]
+fn fcb_at_the_top_of_memory() {
+    // The record fields of an FCB at 0FFF0H wrap round to the bottom of
+    // memory rather than stopping the emulator.
+    let source = "
+        ORG 100H
+        LXI D,0FFF0H
+        MVI C,35
+        CALL 5
+        LXI D,0FFF0H
+        MVI C,36
+        CALL 5
+        LXI D,0FFF0H
+        MVI C,33
+        CALL 5
+        LXI D,0FFF0H
+        MVI C,20
+        CALL 5
+        LXI D,0FFF0H
+        MVI C,34
+        CALL 5
+        JMP 0
+";
+    let scratch = Scratch::new("top");
+    let directory = scratch.path();
+    assert_eq!(run(source, "", b"", directory), "");
+}
+
+#[test]
+fn print_string_without_a_dollar() {
+    // Prints from 0FFF0H, with no $ between there and the end of memory.
+    let source = "
+        ORG 100H
+        LXI D,0FFF0H
+        MVI C,9
+        CALL 5
+        JMP 0
+";
+    let scratch = Scratch::new("dollar");
+    let directory = scratch.path();
+    assert_eq!(run(source, "", b"", directory), "\0".repeat(16));
+}
+
+#[test]
+fn console_status_says_whether_a_key_is_waiting() {
+    // BDOS 11, the BIOS console status entry at 0FF06H and BDOS 6 with 0FFH,
+    // each printed as 0 or 1.
+    let source = "
+        ORG 100H
+        MVI C,11
+        CALL 5
+        CALL PRINT
+        CALL 0FF06H
+        CALL PRINT
+        MVI C,6
+        MVI E,0FFH
+        CALL 5
+        CALL PRINT
+        JMP 0
+PRINT:  ANI 1
+        ADI '0'
+        MOV E,A
+        MVI C,2
+        JMP 5
+";
+    let scratch = Scratch::new("status");
+    assert_eq!(
+        run_with_input(source, "", Box::new(Idle), scratch.path()),
+        "000"
+    );
+}
+
+#[test]
+fn waits_for_a_key_with_console_status() {
+    let program = assemble(
+        "
+        ORG 100H
+WAIT:   MVI C,11
+        CALL 5
+        ORA A
+        JZ WAIT
+        MVI C,1
+        CALL 5
+        JMP 0
+",
+    )
+    .unwrap();
+    let scratch = Scratch::new("wait");
+    let mut machine = Cpm::new(&program.bytes, "", scratch.path().to_path_buf());
+    let console = Console::default();
+    machine.set_console(Box::new(&b"K"[..]), Box::new(console.clone()));
+    // However long the key takes to turn up, it's read once it has.
+    machine.run();
+    assert_eq!(console.text(), "K");
+}
@@ -0,0 +1,113 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{assemble, Bus, Cpu, Flags, Image, Ram};
+
+// Assembles `source` at 0 and runs it until it halts.
+fn run(source: &str) -> (Cpu, Ram) {
+    let assembly = assemble(source).unwrap();
+    let mut ram = Ram::from_image(&Image::from_binary(&assembly.bytes, 0).unwrap());
+    let mut cpu = Cpu::new();
+    cpu.sp = 0x1000;
+    let mut steps = 0;
+    while !cpu.halted {
+        cpu.step(&mut ram);
+        steps += 1;
+        assert!(steps < 1000, "{} didn't halt", source);
+    }
+    (cpu, ram)
+}
+
+#[test]
+fn sets_the_flags() {
+    let (cpu, _) = run("MVI A,0FFH\nADI 1\nHLT");
+    assert_eq!(cpu.a, 0);
+    assert_eq!(
+        cpu.flags,
+        Flags {
+            sign: false,
+            zero: true,
+            aux_carry: true,
+            parity: true,
+            carry: true,
+        }
+    );
+    // Borrowing sets carry.
+    let (cpu, _) = run("MVI A,1\nSUI 2\nHLT");
+    assert_eq!(cpu.a, 0xff);
+    assert!(cpu.flags.carry && cpu.flags.sign && !cpu.flags.zero);
+    // INR leaves carry alone.
+    let (cpu, _) = run("STC\nMVI B,0FFH\nINR B\nHLT");
+    assert_eq!(cpu.b, 0);
+    assert!(cpu.flags.carry && cpu.flags.zero);
+    let (cpu, _) = run("MVI A,19H\nADI 28H\nDAA\nHLT");
+    assert_eq!(cpu.a, 0x47);
+    assert_eq!(Flags::default().to_byte(), 0x02);
+    assert_eq!(Flags::from_byte(0xd7).to_byte(), 0xd7);
+}
+
+#[test]
+fn moves_data_around() {
+    let (cpu, mut ram) = run("
+        LXI H,200H
+        MVI M,42H
+        LDA 200H
+        MOV B,A
+        LXI D,1234H
+        XCHG
+        SHLD 202H
+        LHLD 200H
+        HLT
+");
+    assert_eq!((cpu.a, cpu.b), (0x42, 0x42));
+    assert_eq!(cpu.de(), 0x0200);
+    assert_eq!(cpu.hl(), 0x0042);
+    assert_eq!((ram.read(0x202), ram.read(0x203)), (0x34, 0x12));
+}
+
+#[test]
+fn uses_the_stack() {
+    let (cpu, mut ram) = run("
+        LXI B,1234H
+        PUSH B
+        CALL SWAP
+        POP D
+        HLT
+SWAP:   MVI A,0AAH
+        STC
+        PUSH PSW
+        POP B
+        RET
+");
+    assert_eq!(cpu.sp, 0x1000);
+    assert_eq!(cpu.de(), 0x1234);
+    assert_eq!(cpu.bc(), 0xaa03);
+    // The return address is still there under the stack pointer.
+    assert_eq!((ram.read(0x0ffc), ram.read(0x0ffd)), (0x07, 0x00));
+}
+
+#[test]
+fn counts_cycles() {
+    // MVI 7, ORA 4, CNZ not taken 11, CZ taken 17, HLT 7.
+    let (cpu, _) = run("MVI A,0\nORA A\nCNZ 0\nCZ DONE\nDONE: HLT");
+    assert_eq!(cpu.cycles, 7 + 4 + 11 + 17 + 7);
+}
+
+#[test]
+fn takes_interrupts_after_ei() {
+    let assembly = assemble("EI\nNOP\nHLT").unwrap();
+    let mut ram = Ram::from_image(&Image::from_binary(&assembly.bytes, 0).unwrap());
+    let mut cpu = Cpu::new();
+    cpu.sp = 0x1000;
+    assert!(!cpu.interrupt(&mut ram, 1));
+    cpu.step(&mut ram);
+    // Not until the instruction after EI has run.
+    assert!(!cpu.interrupt(&mut ram, 1));
+    cpu.step(&mut ram);
+    cpu.step(&mut ram);
+    assert!(cpu.halted);
+    assert!(cpu.interrupt(&mut ram, 1));
+    assert!(!cpu.halted && !cpu.interrupts_enabled);
+    assert_eq!(cpu.pc, 0x0008);
+    assert_eq!(cpu.sp, 0x0ffe);
+    assert_eq!(ram.read(0x0ffe), 0x03);
+}
@@ -0,0 +1,84 @@
+// Runs the well known CP/M CPU test programs on the emulator and checks they
+// say they passed. The programs aren't part of the repo, so these only run
+// with `cargo test --release -- --ignored` once they're in tests/roms/
+// (cpudiag.bin, TST8080.COM, 8080PRE.COM, 8080EXM.COM), and fail if any are
+// missing.
+
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use common::{Console, Scratch};
+use eightyeighty_disassembler::Cpm;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Enough for everything except 8080EXM, which gets no limit.
+const STEP_LIMIT: u64 = 100_000_000;
+
+// The program gets a drive of its own, so nothing it does with files can
+// touch anything else.
+fn run_cpm(name: &str, program: &[u8], step_limit: Option<u64>) -> String {
+    let scratch = Scratch::new(name);
+    let mut machine = Cpm::new(program, "", scratch.path().to_path_buf());
+    let console = Console::default();
+    machine.set_console(Box::new(io::empty()), Box::new(console.clone()));
+    let mut steps = 0;
+    while !machine.finished() {
+        machine.step();
+        steps += 1;
+        if step_limit.is_some_and(|limit| steps > limit) {
+            panic!(
+                "Still running after {} steps, output so far:\n{}",
+                steps,
+                console.text()
+            );
+        }
+    }
+    console.text()
+}
+
+fn run_rom(name: &str, step_limit: Option<u64>) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("roms")
+        .join(name);
+    match fs::read(&path) {
+        Ok(program) => run_cpm(name, &program, step_limit),
+        Err(error) => panic!("{} is needed at {}: {}", name, path.display(), error),
+    }
+}
+
+#[test]
+#[ignore = "needs cpudiag.bin in tests/roms"]
+fn cpudiag() {
+    let output = run_rom("cpudiag.bin", Some(STEP_LIMIT));
+    assert!(output.contains("CPU IS OPERATIONAL"), "{}", output);
+}
+
+#[test]
+#[ignore = "needs TST8080.COM in tests/roms"]
+fn tst8080() {
+    let output = run_rom("TST8080.COM", Some(STEP_LIMIT));
+    assert!(output.contains("CPU IS OPERATIONAL"), "{}", output);
+}
+
+#[test]
+#[ignore = "needs 8080PRE.COM in tests/roms"]
+fn preliminary() {
+    let output = run_rom("8080PRE.COM", Some(STEP_LIMIT));
+    assert!(
+        output.contains("8080 Preliminary tests complete"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+#[ignore = "needs 8080EXM.COM in tests/roms, and takes a long time"]
+fn exerciser() {
+    let output = run_rom("8080EXM.COM", None);
+    assert!(output.contains("Tests complete"), "{}", output);
+    assert!(!output.contains("ERROR"), "{}", output);
+}
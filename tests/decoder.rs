@@ -0,0 +1,156 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::listing::{self, DataFormat};
+use eightyeighty_disassembler::{
+    decode, DecodeError, Image, Line, Mnemonic, Operand, Register, RegisterPair,
+};
+
+// The bytes, and what they disassemble to, from Intel's 8080 manual.
+const KNOWN: &[(&[u8], &str)] = &[
+    (&[0x00], "00: NOP"),
+    (&[0x01, 0x34, 0x12], "01: LXI B,#1234"),
+    (&[0x02], "02: STAX B"),
+    (&[0x06, 0x05], "06: MVI B,#5"),
+    (&[0x0a], "0a: LDAX B"),
+    (&[0x22, 0x00, 0x20], "22: SHLD #2000"),
+    (&[0x27], "27: DAA"),
+    (&[0x2a, 0xff, 0x20], "2a: LHLD #20ff"),
+    (&[0x31, 0x00, 0x24], "31: LXI SP,#2400"),
+    (&[0x32, 0x72, 0x20], "32: STA #2072"),
+    (&[0x36, 0x1c], "36: MVI M,#1c"),
+    (&[0x39], "39: DAD SP"),
+    (&[0x3a, 0x67, 0x20], "3a: LDA #2067"),
+    (&[0x41], "41: MOV B,C"),
+    (&[0x76], "76: HLT"),
+    (&[0x77], "77: MOV M,A"),
+    (&[0x7e], "7e: MOV A,M"),
+    (&[0x86], "86: ADD M"),
+    (&[0x9f], "9f: SBB A"),
+    (&[0xa8], "a8: XRA B"),
+    (&[0xbe], "be: CMP M"),
+    (&[0xc2, 0x05, 0x00], "c2: JNZ #5"),
+    (&[0xc3, 0xd4, 0x18], "c3: JMP #18d4"),
+    (&[0xc5], "c5: PUSH B"),
+    (&[0xc9], "c9: RET"),
+    (&[0xcd, 0x05, 0x00], "cd: CALL #5"),
+    (&[0xcf], "cf: RST 1"),
+    (&[0xd3, 0x06], "d3: OUT #6"),
+    (&[0xdb, 0x01], "db: IN #1"),
+    (&[0xe3], "e3: XTHL"),
+    (&[0xe6, 0x0f], "e6: ANI #f"),
+    (&[0xe9], "e9: PCHL"),
+    (&[0xeb], "eb: XCHG"),
+    (&[0xf1], "f1: POP PSW"),
+    (&[0xf3], "f3: DI"),
+    (&[0xf9], "f9: SPHL"),
+    (&[0xfb], "fb: EI"),
+    (&[0xfe, 0x3a], "fe: CPI #3a"),
+    (&[0xff], "ff: RST 7"),
+];
+
+#[test]
+fn decodes_known_encodings() {
+    for &(bytes, text) in KNOWN {
+        let instruction = decode(bytes, 0).unwrap();
+        assert_eq!(instruction.to_string(), text);
+        assert_eq!(instruction.length as usize, bytes.len(), "{}", text);
+        assert!(!instruction.undocumented, "{}", text);
+    }
+    // Extra bytes after the instruction are left alone.
+    let instruction = decode(&[0x3e, 0x41, 0xc9], 0x100).unwrap();
+    assert_eq!(instruction.address, 0x100);
+    assert_eq!(instruction.opcode, 0x3e);
+    assert_eq!(instruction.mnemonic, Mnemonic::Mvi);
+    assert_eq!(
+        instruction.operands,
+        vec![Operand::Reg(Register::A), Operand::Byte(0x41)]
+    );
+    assert_eq!(instruction.length, 2);
+    let instruction = decode(&[0x11, 0xcd, 0xab], 0).unwrap();
+    assert_eq!(
+        instruction.operands,
+        vec![Operand::Pair(RegisterPair::D), Operand::Word(0xabcd)]
+    );
+}
+
+#[test]
+fn decodes_every_opcode() {
+    let mut undocumented = Vec::new();
+    for opcode in 0..=255u8 {
+        let instruction = decode(&[opcode, 0x34, 0x12], 0).unwrap();
+        assert_eq!(instruction.opcode, opcode);
+        let expected = match opcode {
+            // MVI and the immediate arithmetic, IN and OUT.
+            _ if opcode & 0xc7 == 0x06 || opcode & 0xc7 == 0xc6 => 2,
+            0xd3 | 0xdb => 2,
+            // LXI, the direct loads and stores, and the jumps and calls.
+            _ if opcode & 0xcf == 0x01 => 3,
+            0x22 | 0x2a | 0x32 | 0x3a => 3,
+            _ if opcode & 0xc7 == 0xc2 || opcode & 0xc7 == 0xc4 => 3,
+            0xc3 | 0xcb | 0xcd | 0xdd | 0xed | 0xfd => 3,
+            _ => 1,
+        };
+        assert_eq!(instruction.length, expected, "{}", instruction);
+        if instruction.undocumented {
+            undocumented.push(opcode);
+        }
+    }
+    assert_eq!(
+        undocumented,
+        vec![0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38, 0xcb, 0xd9, 0xdd, 0xed, 0xfd]
+    );
+}
+
+#[test]
+fn undocumented_opcodes_do_what_the_documented_ones_do() {
+    let same = |alias: u8, documented: u8| {
+        let alias = decode(&[alias, 0x34, 0x12], 0).unwrap();
+        let documented = decode(&[documented, 0x34, 0x12], 0).unwrap();
+        assert_eq!(
+            (alias.mnemonic, &alias.operands, alias.length),
+            (documented.mnemonic, &documented.operands, documented.length)
+        );
+    };
+    for &alias in &[0x08, 0x10, 0x18, 0x20, 0x28, 0x30, 0x38] {
+        same(alias, 0x00);
+    }
+    same(0xcb, 0xc3);
+    same(0xd9, 0xc9);
+    for &alias in &[0xdd, 0xed, 0xfd] {
+        same(alias, 0xcd);
+    }
+}
+
+#[test]
+fn reports_truncated_instructions() {
+    assert_eq!(decode(&[], 0), Err(DecodeError::Empty));
+    assert_eq!(
+        decode(&[0xc3, 0x00], 0x1ffe),
+        Err(DecodeError::Truncated {
+            address: 0x1ffe,
+            opcode: 0xc3,
+            length: 3,
+            available: 2,
+        })
+    );
+    assert_eq!(
+        decode(&[0x3e], 0x10).unwrap_err().to_string(),
+        "Truncated instruction 3e at 10: needs 2 bytes but only 1 left"
+    );
+    // Whatever's left at the end of the file is shown as data.
+    let image = Image::from_binary(&[0x00, 0xcd, 0x05], 0x100).unwrap();
+    let lines = listing::linear(&image);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[1],
+        Line::Data {
+            address: 0x101,
+            bytes: vec![0xcd, 0x05],
+            format: DataFormat::Bytes,
+            warning: Some(
+                "Truncated instruction cd at 101: needs 3 bytes but only 2 left".to_string()
+            ),
+            comment: None,
+        }
+    );
+}
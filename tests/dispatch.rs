@@ -0,0 +1,102 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::dispatch::{self, Target};
+use eightyeighty_disassembler::{assemble, listing, trace, Image, Line};
+
+// The instructions before the PCHL at the end of `source`.
+fn resolve(source: &str) -> Option<Target> {
+    let lines = common::lines(source);
+    let pchl = lines.last().unwrap().address();
+    dispatch::pchl_targets(&lines).get(&pchl).cloned()
+}
+
+#[test]
+fn follows_registers_into_pchl() {
+    assert_eq!(resolve("LXI H,1234H\nPCHL"), Some(Target::Address(0x1234)));
+    assert_eq!(
+        resolve("LXI D,1234H\nMVI A,0\nXCHG\nPCHL"),
+        Some(Target::Address(0x1234))
+    );
+    // Index in DE, entry loaded into DE then swapped into HL.
+    assert_eq!(
+        resolve("LXI H,2000H\nDAD D\nMOV E,M\nINX H\nMOV D,M\nXCHG\nPCHL"),
+        Some(Target::Table(0x2000))
+    );
+    // Index doubled in A, entry put together in HL by way of A.
+    assert_eq!(
+        resolve(
+            "ADD A\nMOV C,A\nMVI B,0\nLXI H,2000H\nDAD B\nMOV A,M\nINX H\nMOV H,M\nMOV L,A\nPCHL"
+        ),
+        Some(Target::Table(0x2000))
+    );
+    // The table in DE, the index in HL.
+    assert_eq!(
+        resolve("MOV L,A\nMVI H,0\nDAD H\nLXI D,2000H\nDAD D\nMOV E,M\nINX H\nMOV D,M\nXCHG\nPCHL"),
+        Some(Target::Table(0x2000))
+    );
+    assert_eq!(resolve("LHLD 2000H\nPCHL"), None);
+    assert_eq!(resolve("LXI H,2000H\nDAD D\nPCHL"), None);
+}
+
+#[test]
+fn traces_through_jump_tables() {
+    let image = common::image(
+        "
+        ORG 0
+        LXI H,TABLE
+        DAD D
+        MOV E,M
+        INX H
+        MOV D,M
+        XCHG
+        PCHL
+TABLE:  DW ONE, TWO
+ONE:    MVI B,1
+        RET
+TWO:    MVI B,2
+        RET
+",
+    );
+    let traced = trace::follow(&image, &[0]);
+    assert_eq!(
+        traced.tables.into_iter().collect::<Vec<_>>(),
+        vec![(0x09, vec![0x0d, 0x10])]
+    );
+    let addresses: Vec<u16> = traced.instructions.keys().cloned().collect();
+    assert_eq!(
+        addresses,
+        vec![0x00, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x0d, 0x0f, 0x10, 0x12]
+    );
+    // The table itself is left as data.
+    let lines = listing::traced(&image, &traced.instructions);
+    assert!(lines
+        .iter()
+        .any(|line| matches!(*line, Line::Data { address: 0x09, .. })));
+}
+
+#[test]
+fn jump_table_at_the_top_of_memory() {
+    let program = assemble(
+        "
+        LXI H,0FFFCH
+        DAD D
+        MOV E,M
+        INX H
+        MOV D,M
+        XCHG
+        PCHL
+        ORG 20H
+        RET
+",
+    )
+    .unwrap();
+    let mut bytes = program.bytes;
+    bytes.resize(0x10000, 0);
+    bytes[0xfffc..].copy_from_slice(&[0x20, 0, 0x20, 0]);
+    let image = Image::from_binary(&bytes, 0).unwrap();
+    let traced = trace::follow(&image, &[0]);
+    assert_eq!(traced.tables.get(&0xfffc), Some(&vec![0x20, 0x20]));
+    assert_eq!(traced.table_ranges(), vec![(0xfffc, 0xffff)]);
+}
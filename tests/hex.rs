@@ -0,0 +1,118 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::hex;
+use eightyeighty_disassembler::Region;
+
+// A record line with its checksum worked out.
+fn record(kind: u8, address: u16, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8, (address >> 8) as u8, address as u8, kind];
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+    bytes.push(sum.wrapping_neg());
+    let digits: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    format!(":{}", digits.concat())
+}
+
+#[test]
+fn reads_data_records() {
+    let text = "
+:10010000214601360121470136007EFE09D2190140
+:100110002146017E17C20001FF5F16002148011928
+:00000001FF
+:0400000000000000FC
+";
+    assert!(hex::is_hex(text.as_bytes()));
+    let file = hex::parse(text).unwrap();
+    assert_eq!(file.start, None);
+    assert_eq!(
+        file.image.regions(),
+        &[Region {
+            start: 0x100,
+            bytes: vec![
+                0x21, 0x46, 0x01, 0x36, 0x01, 0x21, 0x47, 0x01, 0x36, 0x00, 0x7e, 0xfe, 0x09, 0xd2,
+                0x19, 0x01, 0x21, 0x46, 0x01, 0x7e, 0x17, 0xc2, 0x00, 0x01, 0xff, 0x5f, 0x16, 0x00,
+                0x21, 0x48, 0x01, 0x19,
+            ],
+        }]
+    );
+    assert!(!hex::is_hex(&[0xc3, 0x00, 0x01]));
+    assert!(!hex::is_hex(b"10010000"));
+}
+
+#[test]
+fn follows_address_and_start_records() {
+    let text = [
+        record(0x00, 0x0000, &[0xc3, 0x00, 0x10]),
+        // Segment 0x0100 puts everything after it at 0x1000 on.
+        record(0x02, 0x0000, &[0x01, 0x00]),
+        record(0x00, 0x0000, &[0x76]),
+        record(0x05, 0x0000, &[0x00, 0x00, 0x10, 0x00]),
+        record(0x01, 0x0000, &[]),
+    ]
+    .join("\n");
+    let file = hex::parse(&text).unwrap();
+    assert_eq!(file.start, Some(0x1000));
+    let regions: Vec<(u16, &[u8])> = file
+        .image
+        .regions()
+        .iter()
+        .map(|region| (region.start, &region.bytes[..]))
+        .collect();
+    assert_eq!(
+        regions,
+        vec![(0x0000, &[0xc3, 0x00, 0x10][..]), (0x1000, &[0x76][..])]
+    );
+    let text = [
+        record(0x04, 0x0000, &[0x00, 0x01]),
+        record(0x00, 0x0000, &[0x00]),
+    ]
+    .join("\n");
+    assert_eq!(
+        hex::parse(&text).unwrap_err().to_string(),
+        "line 2: Data at 10000 is past the 8080's 64K of memory"
+    );
+}
+
+#[test]
+fn reports_bad_records() {
+    let error = |text: &str| hex::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        error(":10010000214601360121470136007EFE09D2190141"),
+        "line 1: Bad checksum, expected 40"
+    );
+    assert_eq!(
+        error("\n:0200000001FC"),
+        "line 2: Record is the wrong length"
+    );
+    assert_eq!(
+        error("10010000"),
+        "line 1: Record doesn't start with a colon"
+    );
+    assert_eq!(error(":0G"), "line 1: Record isn't made of hex digit pairs");
+    assert_eq!(
+        error(&record(0x07, 0, &[])),
+        "line 1: Unknown record type 07"
+    );
+    assert_eq!(
+        error(&[record(0x00, 0x10, &[1, 2]), record(0x00, 0x11, &[3])].join("\n")),
+        "line 2: 11 is loaded more than once"
+    );
+}
+
+#[test]
+fn rejects_data_past_64k() {
+    let error = |text: &str| hex::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        error(&record(0x00, 0xffff, &[1, 2])),
+        "line 1: Data at ffff is past the 8080's 64K of memory"
+    );
+    // A high linear base mustn't wrap round to the bottom of memory.
+    assert_eq!(
+        error(":02000004FFFFFC\n:01FFFF000001\n:00000001FF"),
+        "line 2: Data at ffffffff is past the 8080's 64K of memory"
+    );
+    assert_eq!(
+        error(&[record(0x02, 0, &[0x10, 0x00]), record(0x00, 0, &[1])].join("\n")),
+        "line 2: Data at 10000 is past the 8080's 64K of memory"
+    );
+}
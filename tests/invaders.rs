@@ -0,0 +1,102 @@
+// Runs little programs on the Space Invaders board in place of the real
+// ROMs, which aren't part of the repo.
+
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{assemble, invaders, png, Bus, Image, Invaders};
+
+fn board(source: &str) -> Invaders {
+    let program = assemble(source).unwrap();
+    Invaders::new(&Image::from_binary(&program.bytes, program.origin).unwrap())
+}
+
+#[test]
+fn interrupts_and_shift_register() {
+    let mut machine = board(
+        "
+        ORG 0
+        JMP START
+        ORG 8
+        JMP HALF
+        ORG 10H
+        JMP VBLANK
+START:  LXI SP,2400H
+        ; Shift 0FF00H left by 3 to get 0F8H.
+        MVI A,0
+        OUT 4
+        MVI A,0FFH
+        OUT 4
+        MVI A,3
+        OUT 2
+        IN 3
+        STA 2000H
+        ; Writing to ROM does nothing, and the mirror is the same RAM.
+        STA 0000H
+        LDA 6000H
+        STA 2001H
+        EI
+LOOP:   JMP LOOP
+HALF:   LXI H,2002H
+        INR M
+        EI
+        RET
+VBLANK: LXI H,2003H
+        INR M
+        EI
+        RET
+",
+    );
+    machine.run_frame();
+    machine.run_frame();
+    let board = &mut machine.board;
+    assert_eq!(board.read(0x2000), 0xf8);
+    assert_eq!(board.read(0x2001), 0xf8);
+    assert_eq!(board.read(0x0000), 0xc3);
+    // One of each a frame, but the last RST 2 comes at the very end so its
+    // handler hasn't run yet.
+    assert_eq!(board.read(0x2002), 2);
+    assert_eq!(board.read(0x2003), 1);
+}
+
+#[test]
+fn screen_is_rotated() {
+    // Bit 0 of the first byte of video RAM is the bottom left corner, and
+    // the next byte carries on up the screen.
+    let mut machine = board(
+        "
+        ORG 0
+        MVI A,1
+        STA 2400H
+        MVI A,80H
+        STA 241FH
+        STA 3FFFH
+        HLT
+",
+    );
+    machine.run_frame();
+    let screen = machine.screen();
+    let lit: Vec<(usize, usize)> = screen
+        .iter()
+        .enumerate()
+        .filter(|&(_, &pixel)| pixel == 0xff)
+        .map(|(index, _)| (index % invaders::WIDTH, index / invaders::WIDTH))
+        .collect();
+    assert_eq!(lit, vec![(0, 0), (223, 0), (0, 255)]);
+}
+
+#[test]
+fn png_structure() {
+    let mut file = Vec::new();
+    png::write_greyscale(&mut file, 2, 2, &[0, 255, 255, 0]).unwrap();
+    assert_eq!(&file[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&file[12..16], b"IHDR");
+    // IEND's CRC never changes.
+    assert_eq!(&file[file.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    // The rows with their filter bytes, stored as is, then the Adler-32.
+    let data_start = 8 + 25 + 8;
+    assert_eq!(&file[data_start - 4..data_start], b"IDAT");
+    assert_eq!(
+        &file[data_start..data_start + 17],
+        &[0x78, 0x01, 1, 6, 0, 0xf9, 0xff, 0, 0, 255, 0, 255, 0, 0x06, 0x00, 0x01, 0xff][..]
+    );
+}
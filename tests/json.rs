@@ -0,0 +1,58 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::json::{self, Style};
+use eightyeighty_disassembler::{labels, listing, map};
+use std::collections::BTreeMap;
+
+const PROGRAM: &str = "
+        ORG 100H
+START:  MVI C,22H
+        CZ START
+        LXI H,START
+        PCHL
+        DB 1,2
+";
+
+fn write(style: Style, comments: &BTreeMap<u16, Vec<String>>) -> String {
+    let image = common::image(PROGRAM);
+    let lines = listing::mapped(
+        &image,
+        &map::parse("0x109-0x10a bytes").unwrap(),
+        &listing::linear(&image),
+    );
+    let mut out = Vec::new();
+    json::write(&mut out, &lines, &labels::generate(&lines), comments, style).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn writes_one_object_a_line() {
+    let mut comments = BTreeMap::new();
+    comments.insert(0x109, vec!["say \"hi\"".to_string()]);
+    assert_eq!(
+        write(Style::Lines, &comments),
+        r#"{"format":"eightyeighty-disassembler","version":1}
+{"type":"instruction","address":256,"bytes":[14,34],"length":2,"label":"SUB_0100","mnemonic":"MVI","operands":[{"type":"register","value":"C","label":null},{"type":"byte","value":34,"label":null}],"cycles":7,"cycles_taken":null,"flow":{"kind":"next","targets":[]},"undocumented":false,"comments":[]}
+{"type":"instruction","address":258,"bytes":[204,0,1],"length":3,"label":null,"mnemonic":"CZ","operands":[{"type":"address","value":256,"label":"SUB_0100"}],"cycles":11,"cycles_taken":17,"flow":{"kind":"conditional_call","targets":[256,261]},"undocumented":false,"comments":[]}
+{"type":"instruction","address":261,"bytes":[33,0,1],"length":3,"label":null,"mnemonic":"LXI","operands":[{"type":"pair","value":"H","label":null},{"type":"word","value":256,"label":"SUB_0100"}],"cycles":10,"cycles_taken":null,"flow":{"kind":"next","targets":[]},"undocumented":false,"comments":[]}
+{"type":"instruction","address":264,"bytes":[233],"length":1,"label":null,"mnemonic":"PCHL","operands":[],"cycles":5,"cycles_taken":null,"flow":{"kind":"indirect","targets":[256]},"undocumented":false,"comments":[]}
+{"type":"data","address":265,"bytes":[1,2],"length":2,"label":null,"format":"bytes","values":[1,2],"warning":null,"comments":["say \"hi\""]}
+"#
+    );
+}
+
+#[test]
+fn writes_a_document() {
+    let document = write(Style::Document, &BTreeMap::new());
+    assert!(
+        document
+            .starts_with("{\"format\":\"eightyeighty-disassembler\",\"version\":1,\"lines\":[\n"),
+        "{}",
+        document
+    );
+    assert!(document.ends_with("\"comments\":[]}\n]}\n"), "{}", document);
+    // Every object but the last is followed by a comma.
+    assert_eq!(document.matches("},\n").count(), 4, "{}", document);
+}
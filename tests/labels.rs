@@ -0,0 +1,42 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{labels, listing, Image, Line};
+
+const PROGRAM: &[u8] = &[
+    0xcd, 0x0a, 0x00, // 0000: CALL 000A
+    0xc3, 0x0b, 0x00, // 0003: JMP 000B
+    0xc2, 0x07, 0x00, // 0006: JNZ 0007, the middle of itself
+    0x00, // 0009: NOP
+    0xc9, // 000a: RET
+    0xca, 0x0a, 0x00, // 000b: JZ 000A
+    0xff, // 000e: RST 7, outside the program
+];
+
+fn lines() -> Vec<Line> {
+    listing::linear(&Image::from_binary(PROGRAM, 0).unwrap())
+}
+
+#[test]
+fn names_jump_and_call_destinations() {
+    let labels = labels::generate(&lines());
+    // Called as well as jumped to, so it's a subroutine.
+    assert_eq!(
+        labels.iter().collect::<Vec<_>>(),
+        vec![(0x0a, "SUB_000A"), (0x0b, "L_000B")]
+    );
+    assert_eq!(labels.get(0x07), None);
+    assert_eq!(labels.get(0x38), None);
+}
+
+#[test]
+fn labels_replace_addresses() {
+    let lines = lines();
+    let labels = labels::generate(&lines);
+    let text = |line: &Line| match *line {
+        Line::Instruction(ref instruction) => instruction.with_labels(&labels).to_string(),
+        Line::Data { .. } => panic!("{:?}", line),
+    };
+    assert_eq!(text(&lines[0]), "cd: CALL SUB_000A");
+    assert_eq!(text(&lines[1]), "c3: JMP L_000B");
+    assert_eq!(text(&lines[2]), "c2: JNZ #7");
+}
@@ -0,0 +1,126 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::listing::{self, DataFormat, Line};
+use eightyeighty_disassembler::map::{self, Kind};
+use eightyeighty_disassembler::{assemble, labels, source, Image};
+
+const PROGRAM: &str = "
+        ORG 0
+START:  LXI H,TABLE
+        CALL SHOW
+        JMP START
+SHOW:   RET
+TABLE:  DW START, SHOW
+        DW 1234H
+        DB 'Hi', 13, 10, 0
+        DB 1, 2, 3
+";
+
+const MAP: &str = "
+# The table and text after the code
+0x0000-0x0009 code
+0x000a-0x000d pointers  jump table
+0x000e-0x000f words
+0x0010-0x0014 ascii
+0x0015-0x0017 bytes
+";
+
+#[test]
+fn parses_a_map() {
+    let map = map::parse(MAP).unwrap();
+    let kinds: Vec<(u16, u16, Kind)> = map
+        .ranges()
+        .iter()
+        .map(|range| (range.start, range.end, range.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0x00, 0x09, Kind::Code),
+            (0x0a, 0x0d, Kind::Pointers),
+            (0x0e, 0x0f, Kind::Words),
+            (0x10, 0x14, Kind::Ascii),
+            (0x15, 0x17, Kind::Bytes),
+        ]
+    );
+    assert_eq!(map.ranges()[1].note, "jump table");
+
+    let error = |text: &str| map::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        error("0x10-0x1f code\n0x18-0x20 bytes"),
+        "line 2: 0018-0020 overlaps 0010-001f"
+    );
+    assert_eq!(
+        error("0x20-0x10 code"),
+        "line 1: Bad address range 0x20-0x10"
+    );
+    assert_eq!(
+        error("0x10-0x20 sprites"),
+        "line 1: Unknown kind sprites, expected code, bytes, words, pointers, ascii or unknown"
+    );
+}
+
+#[test]
+fn data_ranges_become_data_lines() {
+    let image = common::image(PROGRAM);
+    let map = map::parse(MAP).unwrap();
+    let lines = listing::mapped(&image, &map, &listing::linear(&image));
+    let formats: Vec<(u16, Option<DataFormat>)> = lines
+        .iter()
+        .map(|line| match *line {
+            Line::Instruction(_) => (line.address(), None),
+            Line::Data { format, .. } => (line.address(), Some(format)),
+        })
+        .collect();
+    assert_eq!(
+        formats,
+        vec![
+            (0x00, None),
+            (0x03, None),
+            (0x06, None),
+            (0x09, None),
+            (0x0a, Some(DataFormat::Pointers)),
+            (0x0e, Some(DataFormat::Words)),
+            (0x10, Some(DataFormat::Text)),
+            (0x15, Some(DataFormat::Bytes)),
+        ]
+    );
+
+    let labels = labels::generate(&lines);
+    let mut text = Vec::new();
+    source::write(&mut text, &lines, &labels).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("DW      L_0000,SUB_0009\n"), "{}", text);
+    assert!(text.contains("DW      1234H\n"), "{}", text);
+    assert!(text.contains("DB      \"Hi\",0DH,0AH,00H\n"), "{}", text);
+    assert_eq!(assemble(&text).unwrap(), assemble(PROGRAM).unwrap());
+}
+
+#[test]
+fn lines_cut_by_the_map_become_bytes() {
+    // JMP 0 decoded at 0, but the map says its last byte is data.
+    let image = Image::from_binary(&[0xc3, 0x00, 0x00, 0x00], 0).unwrap();
+    let map = map::parse("2-3 words").unwrap();
+    let lines = listing::mapped(&image, &map, &listing::linear(&image));
+    assert_eq!(
+        lines,
+        vec![
+            Line::Data {
+                address: 0,
+                bytes: vec![0xc3, 0x00],
+                format: DataFormat::Bytes,
+                warning: None,
+                comment: None,
+            },
+            Line::Data {
+                address: 2,
+                bytes: vec![0x00, 0x00],
+                format: DataFormat::Words,
+                warning: None,
+                comment: None,
+            },
+        ]
+    );
+}
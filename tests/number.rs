@@ -0,0 +1,41 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::number;
+
+#[test]
+fn reads_every_way_of_writing_a_number() {
+    for text in &[
+        "0x1a",
+        "0X1A",
+        "$1a",
+        "1AH",
+        "1ah",
+        "00011010B",
+        "32Q",
+        "32o",
+        "26",
+        "26D",
+    ] {
+        assert_eq!(number::parse(text), Some(26), "{}", text);
+    }
+    assert_eq!(number::parse("0FFH"), Some(255));
+    for text in &["", "H", "0x", "+5", "-5", "$-5", "12A", "2B+1", "0x1 2"] {
+        assert_eq!(number::parse(text), None, "{}", text);
+    }
+}
+
+#[test]
+fn reads_addresses_as_hex() {
+    for text in &["1a00", "1A00", "0x1a00", "$1a00", "1a00h", "1A00H"] {
+        assert_eq!(number::address(text), Some(0x1a00), "{}", text);
+    }
+    // As the listing prints them, not binary or decimal.
+    assert_eq!(number::address("1b"), Some(0x1b));
+    assert_eq!(number::address("100"), Some(0x100));
+    assert_eq!(number::address("ffff"), Some(0xffff));
+    for text in &[
+        "", "0x", "H", "10000", "10000H", "-1", "+1", "1a00-", "0x1g",
+    ] {
+        assert_eq!(number::address(text), None, "{}", text);
+    }
+}
@@ -0,0 +1,69 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{labels, listing, Image, ImageError};
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+// JMP to the RET straight after it.
+const PROGRAM: &[u8] = &[0xc3, 0x03, 0x01, 0xc9];
+
+#[test]
+fn addresses_start_at_the_origin() {
+    let image = Image::from_binary(PROGRAM, 0x100).unwrap();
+    let lines = listing::linear(&image);
+    let addresses: Vec<u16> = lines.iter().map(|line| line.address()).collect();
+    assert_eq!(addresses, vec![0x100, 0x103]);
+    let labels = labels::generate(&lines);
+    assert_eq!(labels.get(0x103), Some("L_0103"));
+    // Loaded at zero the jump goes past the end, so it gets no label.
+    let lines = listing::linear(&Image::from_binary(PROGRAM, 0).unwrap());
+    assert!(labels::generate(&lines).is_empty());
+    assert_eq!(
+        Image::from_binary(PROGRAM, 0xfffe),
+        Err(ImageError::TooBig {
+            start: 0xfffe,
+            length: 4
+        })
+    );
+}
+
+#[test]
+fn origin_option() {
+    let path = env::temp_dir().join(format!("origin-{}.bin", process::id()));
+    fs::write(&path, PROGRAM).unwrap();
+    let run = |arguments: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_eightyeighty-disassembler"))
+            .args(arguments)
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let hex = run(&["--origin", "0x100"]);
+    let intel = run(&["--origin", "100h", "--offsets"]);
+    let too_high = run(&["--origin", "0xfffe"]);
+    let bad = run(&["--origin", "nowhere"]);
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(hex.stdout).unwrap();
+    assert!(
+        stdout.starts_with("100     c3: JMP L_0103\nL_0103:\n103     c9: RET\n"),
+        "{}",
+        stdout
+    );
+    // With --offsets the place in the file still starts from zero.
+    let stdout = String::from_utf8(intel.stdout).unwrap();
+    assert!(
+        stdout.starts_with("0  100     c3: JMP L_0103\n"),
+        "{}",
+        stdout
+    );
+    assert!(!too_high.status.success());
+    assert!(String::from_utf8(too_high.stderr)
+        .unwrap()
+        .contains("4 bytes at fffe goes past the end of memory"));
+    assert!(!bad.status.success());
+    assert!(String::from_utf8(bad.stderr)
+        .unwrap()
+        .contains("Bad origin: nowhere"));
+}
@@ -0,0 +1,127 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::platform::{self, Area, Direction, Machine, Port};
+use eightyeighty_disassembler::{decode, Labels, Platform};
+
+#[test]
+fn parses_a_platform_file() {
+    let platform = platform::parse(
+        r#"
+# A made up board.
+name = "Test # board"
+origin = 0x1000
+
+[in]
+0x10 = "KEYS"
+
+[out]
+0x11 = ["LEDS", "front \"panel\" lights"] # on the lid
+
+[ports]
+0x12 = ["SERIAL"]
+
+[memory]
+0x8000-0x9fff = "screen"
+"#,
+    )
+    .unwrap();
+    assert_eq!(platform.name, "Test # board");
+    assert_eq!(platform.origin, 0x1000);
+    assert!(!platform.bdos);
+    let port = |direction, number, name: &str, description: &str| Port {
+        direction,
+        number,
+        name: name.to_string(),
+        description: description.to_string(),
+    };
+    assert_eq!(
+        platform.ports,
+        vec![
+            port(Direction::In, 0x10, "KEYS", ""),
+            port(Direction::Out, 0x11, "LEDS", "front \"panel\" lights"),
+            port(Direction::In, 0x12, "SERIAL", ""),
+            port(Direction::Out, 0x12, "SERIAL", ""),
+        ]
+    );
+    assert_eq!(
+        platform.areas,
+        vec![Area {
+            start: 0x8000,
+            end: 0x9fff,
+            name: "screen".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn reports_mistakes_with_their_line() {
+    let error = |text: &str| platform::parse(text).unwrap_err().to_string();
+    assert_eq!(error("[disk]"), "line 1: Unknown section [disk]");
+    assert_eq!(
+        error("\n[in]\n0x100 = \"BIG\""),
+        "line 3: Bad port number 0x100"
+    );
+    assert_eq!(
+        error("[in]\n1 = \"KEYS\"\n2 = \"KEYS\""),
+        "line 3: KEYS is already port 01"
+    );
+    assert_eq!(
+        error("[in]\n1 = \"KEYS\"\n1 = \"DIPS\""),
+        "line 3: Port 01 is already KEYS"
+    );
+    assert_eq!(
+        error("[out]\n3 = \"SOUND\"\n[ports]\n3 = \"SERIAL\""),
+        "line 4: Port 03 is already SOUND"
+    );
+    assert_eq!(error("name = \"open"), "line 1: String doesn't end");
+    assert_eq!(
+        error("[out]\n2 = \"SP\""),
+        "line 2: SP can't be used as a name"
+    );
+    assert_eq!(
+        error("[in]\n2 = \"2IN\""),
+        "line 2: 2IN can't be used as a name"
+    );
+    assert_eq!(
+        error("[memory]\n0x2000-0x1000 = \"RAM\""),
+        "line 2: Bad address range 0x2000-0x1000"
+    );
+}
+
+#[test]
+fn says_what_to_run_on() {
+    let invaders = Platform::builtin("invaders").unwrap();
+    assert_eq!(invaders.machine, Some(Machine::Invaders));
+    assert_eq!(Platform::builtin("cpm").unwrap().machine, None);
+    // Copying the ports and memory isn't enough, it has to say so.
+    let copy = platform::parse("name = \"Space Invaders\"\n[in]\n0x01 = \"INP1\"").unwrap();
+    assert_eq!(copy.machine, None);
+    let file = platform::parse("name = \"Mine\"\nmachine = \"invaders\"").unwrap();
+    assert_eq!(file.machine, Some(Machine::Invaders));
+    assert_eq!(
+        platform::parse("machine = \"apple\"")
+            .unwrap_err()
+            .to_string(),
+        "line 1: Unknown machine apple"
+    );
+}
+
+#[test]
+fn ports_are_named_in_listings() {
+    for name in Platform::builtin_names() {
+        assert!(Platform::builtin(name).is_some(), "{}", name);
+    }
+    let platform = Platform::builtin("invaders").unwrap();
+    let mut labels = Labels::new();
+    for port in &platform.ports {
+        labels.insert_port(port.direction, port.number, port.name.clone());
+    }
+    // Port 3 is different going in and out.
+    let out = decode(&[0xd3, 0x03], 0).unwrap();
+    let input = decode(&[0xdb, 0x03], 0).unwrap();
+    assert_eq!(out.with_labels(&labels).to_string(), "d3: OUT SOUND1");
+    assert_eq!(input.with_labels(&labels).to_string(), "db: IN SHFT_IN");
+    assert_eq!(platform.comments(&out), vec!["sound bits"]);
+    let store = decode(&[0x32, 0x00, 0x24], 0).unwrap();
+    assert_eq!(platform.comments(&store), vec!["video RAM"]);
+}
@@ -0,0 +1,99 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{listing, Image, ImageError};
+use std::env;
+use std::fs;
+use std::process::{self, Command, Stdio};
+
+#[test]
+fn joins_regions_that_touch() {
+    let mut image = Image::new();
+    image.add(0x1000, &[0xc9]).unwrap();
+    image.add(0x0000, &[0x00, 0xcd]).unwrap();
+    // The CALL's address is in the next chip.
+    image.add(0x0002, &[0x00, 0x10]).unwrap();
+    let regions: Vec<(u16, usize)> = image
+        .regions()
+        .iter()
+        .map(|region| (region.start, region.bytes.len()))
+        .collect();
+    assert_eq!(regions, vec![(0x0000, 4), (0x1000, 1)]);
+    let lines = listing::linear(&image);
+    let addresses: Vec<u16> = lines.iter().map(|line| line.address()).collect();
+    assert_eq!(addresses, vec![0x0000, 0x0001, 0x1000]);
+    assert_eq!(image.byte(0x0003), Some(0x10));
+    assert_eq!(image.byte(0x0004), None);
+    assert_eq!(image.add(0x0fff, &[0, 0]), Err(ImageError::Overlap(0x1000)));
+    assert_eq!(image.add(0x0002, &[0]), Err(ImageError::Overlap(0x0002)));
+}
+
+#[test]
+fn loads_several_files() {
+    let path = |name: &str| env::temp_dir().join(format!("roms-{}-{}", process::id(), name));
+    let (first, second) = (path("a.bin"), path("b.bin"));
+    fs::write(&first, [0x00, 0x00]).unwrap();
+    fs::write(&second, [0xc9]).unwrap();
+    let run = |arguments: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_eightyeighty-disassembler"))
+            .args(arguments)
+            .output()
+            .unwrap()
+    };
+    let (first, second) = (first.to_str().unwrap(), second.to_str().unwrap());
+    let after = run(&["--offsets", first, second]);
+    let placed = run(&[first, &format!("{}@0x10", second)]);
+    let overlapping = run(&[first, &format!("{}@1", second)]);
+    let missing = path("missing.bin");
+    let missing = missing.to_str().unwrap();
+    let not_there = run(&[first, missing]);
+    fs::remove_file(first).unwrap();
+    fs::remove_file(second).unwrap();
+
+    // Each file goes straight after the one before unless it says otherwise.
+    let stdout = String::from_utf8(after.stdout).unwrap();
+    assert!(
+        stdout.contains(&format!(
+            "{}:1  1     00: NOP\n{}:0  2     c9: RET\n",
+            first, second
+        )),
+        "{}",
+        stdout
+    );
+    let stdout = String::from_utf8(placed.stdout).unwrap();
+    assert!(
+        stdout.contains("1     00: NOP\n10     c9: RET\n"),
+        "{}",
+        stdout
+    );
+    let stderr = String::from_utf8(placed.stderr).unwrap();
+    assert!(stderr.contains("note: nothing loaded from 0002 to 000f"));
+    assert!(!overlapping.status.success());
+    let stderr = String::from_utf8(overlapping.stderr).unwrap();
+    assert!(stderr.contains("1 is loaded more than once"), "{}", stderr);
+    // A file that can't be read is reported, not a panic.
+    assert_eq!(not_there.status.code(), Some(1));
+    let stderr = String::from_utf8(not_there.stderr).unwrap();
+    assert!(stderr.starts_with(&format!("{}: ", missing)), "{}", stderr);
+}
+
+#[test]
+fn stops_quietly_when_the_output_is_closed() {
+    let rom = env::temp_dir().join(format!("roms-{}-closed.bin", process::id()));
+    let bytes: Vec<u8> = (0..0x10000).map(|index| index as u8).collect();
+    fs::write(&rom, bytes).unwrap();
+    for format in &["asm", "json", "jsonl", "tree", "dot"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_eightyeighty-disassembler"))
+            .args(["--format", format, rom.to_str().unwrap()])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // Like `| head` giving up after the first few lines.
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(output.status.success(), "{}: {}", format, stderr);
+        assert_eq!(stderr, "", "{}", format);
+    }
+    fs::remove_file(&rom).unwrap();
+}
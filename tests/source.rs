@@ -0,0 +1,51 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::{assemble, decode, labels, listing, source, Image};
+
+// Assembles the source written for `bytes` loaded at `origin`.
+fn round_trip(bytes: &[u8], origin: u16) -> (String, Vec<u8>) {
+    let image = Image::from_binary(bytes, origin).unwrap();
+    let lines = listing::linear(&image);
+    let labels = labels::generate(&lines);
+    let mut text = Vec::new();
+    source::write(&mut text, &lines, &labels).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    let assembly = assemble(&text).unwrap_or_else(|error| panic!("{}\n{}", error, text));
+    assert_eq!(assembly.origin, origin, "{}", text);
+    (text, assembly.bytes)
+}
+
+#[test]
+fn every_opcode_assembles_back_to_its_bytes() {
+    let mut bytes = Vec::new();
+    for opcode in 0..=255u8 {
+        let length = decode(&[opcode, 0, 0], 0).unwrap().length as usize;
+        bytes.extend_from_slice(&[opcode, 0x34, 0x12][..length]);
+    }
+    // A JMP cut off by the end of the file.
+    bytes.extend_from_slice(&[0xc3, 0x00]);
+    let (text, assembled) = round_trip(&bytes, 0x100);
+    assert_eq!(assembled, bytes);
+    assert!(text.starts_with("        ORG     0100H\n"), "{}", text);
+    assert!(
+        text.contains("        DB      0CBH,34H,12H ; JMP     1234H (undocumented)\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("        DB      0C3H,00H ; Truncated instruction"));
+}
+
+#[test]
+fn labels_assemble_to_the_same_addresses() {
+    let program = [
+        0xcd, 0x06, 0x00, // 0000: CALL 0006
+        0xc3, 0x00, 0x00, // 0003: JMP 0000
+        0x21, 0x00, 0x00, // 0006: LXI H,0000
+        0xc9, // 0009: RET
+    ];
+    let (text, assembled) = round_trip(&program, 0);
+    assert_eq!(&assembled[..], &program[..]);
+    assert!(text.contains("SUB_0006:\n"), "{}", text);
+    assert!(text.contains("JMP     L_0000\n"), "{}", text);
+    assert!(text.contains("LXI     H,L_0000\n"), "{}", text);
+}
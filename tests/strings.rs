@@ -0,0 +1,136 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::listing::{self, DataFormat, Line};
+use eightyeighty_disassembler::map::{self, Map};
+use eightyeighty_disassembler::{assemble, labels, platform, source, strings, Charset, Image};
+
+// Everything as data, the way a trace that never reached it leaves it.
+fn data(bytes: &[u8]) -> Vec<Line> {
+    let image = Image::from_binary(bytes, 0).unwrap();
+    listing::traced(&image, &Default::default())
+}
+
+fn formats(lines: &[Line]) -> Vec<(u16, Option<DataFormat>)> {
+    lines
+        .iter()
+        .map(|line| match *line {
+            Line::Instruction(_) => (line.address(), None),
+            Line::Data { format, .. } => (line.address(), Some(format)),
+        })
+        .collect()
+}
+
+#[test]
+fn finds_strings_and_their_terminators() {
+    let program = assemble(
+        "
+        DB 'Hello$', 'world$'
+        DB 1, 2, 3
+        DB 'INSERT COIN', 0
+        DB 'ABC', 0
+        DB 'TOP BI', 'T'+80H
+        DB 'no end'
+",
+    )
+    .unwrap();
+    let lines = strings::find(
+        &data(&program.bytes),
+        &Map::new(),
+        &Charset::ascii(),
+        strings::MIN_LENGTH,
+    );
+    assert_eq!(
+        formats(&lines),
+        vec![
+            (0x00, Some(DataFormat::Text)),
+            (0x06, Some(DataFormat::Text)),
+            (0x0c, Some(DataFormat::Bytes)),
+            (0x0f, Some(DataFormat::Text)),
+            // Too short to be sure.
+            (0x1b, Some(DataFormat::Bytes)),
+            (0x1f, Some(DataFormat::Text)),
+            (0x26, Some(DataFormat::Text)),
+        ]
+    );
+
+    let labels = labels::generate(&lines);
+    let mut text = Vec::new();
+    source::write(&mut text, &lines, &labels).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("DB      \"INSERT COIN\",00H\n"), "{}", text);
+    assert!(text.contains("DB      \"TOP BI\",0D4H\n"), "{}", text);
+    assert_eq!(assemble(&text).unwrap().bytes, program.bytes);
+
+    // Nothing is short enough with a longer minimum.
+    let lines = strings::find(&data(&program.bytes), &Map::new(), &Charset::ascii(), 20);
+    assert!(formats(&lines)
+        .iter()
+        .all(|&(_, format)| format == Some(DataFormat::Bytes)));
+}
+
+#[test]
+fn uses_a_platforms_characters() {
+    let invaders = platform::Platform::builtin("invaders").unwrap();
+    let charset = invaders.charset.unwrap();
+    // "PLAY" then "SPACE  INVADERS" in the game's font.
+    let mut bytes = vec![0x0f, 0x0b, 0x00, 0x18, 0xff];
+    bytes.extend(&[
+        0x12, 0x0f, 0x00, 0x02, 0x04, 0x26, 0x26, 0x08, 0x0d, 0x15, 0x00, 0x03, 0x04, 0x11, 0x12,
+    ]);
+    let lines = strings::find(&data(&bytes), &Map::new(), &charset, strings::MIN_LENGTH);
+    let comments: Vec<(u16, Option<String>)> = lines
+        .iter()
+        .map(|line| match *line {
+            Line::Data { ref comment, .. } => (line.address(), comment.clone()),
+            Line::Instruction(_) => (line.address(), None),
+        })
+        .collect();
+    assert_eq!(
+        comments,
+        vec![
+            (0, Some("\"PLAY\"".to_string())),
+            (4, None),
+            (5, Some("\"SPACE  INVADERS\"".to_string())),
+        ]
+    );
+
+    let error = |text: &str| platform::parse(text).unwrap_err().to_string();
+    assert_eq!(
+        error("[charset]\n0x00-0x02 = \"AB\""),
+        "line 2: 0x00-0x02 is 3 bytes but there are 2 characters"
+    );
+    assert_eq!(
+        error("[charset]\n0x100 = \"A\""),
+        "line 2: Bad byte range 0x100"
+    );
+}
+
+#[test]
+fn leaves_mapped_data_alone() {
+    let bytes = b"HELLO WORLD";
+    let map = map::parse("0-4 bytes\n6-10 unknown").unwrap();
+    let image = Image::from_binary(bytes, 0).unwrap();
+    let lines = listing::mapped(&image, &map, &listing::traced(&image, &Default::default()));
+    let lines = strings::find(&lines, &map, &Charset::ascii(), strings::MIN_LENGTH);
+    assert_eq!(
+        formats(&lines),
+        vec![(0, Some(DataFormat::Bytes)), (5, Some(DataFormat::Text))]
+    );
+}
+
+#[test]
+fn knows_when_a_charset_is_plain_ascii() {
+    assert!(Charset::ascii().is_ascii());
+    assert!(!Charset::new().is_ascii());
+    let mut charset = Charset::new();
+    for byte in 0x20..0x7f {
+        charset.insert(byte, byte as char);
+    }
+    assert!(charset.is_ascii());
+    assert_eq!(charset, Charset::ascii());
+    charset.insert(0x00, '@');
+    assert!(!charset.is_ascii());
+    let mut charset = Charset::ascii();
+    charset.insert(b'A', 'a');
+    assert!(!charset.is_ascii());
+}
@@ -0,0 +1,150 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::{assemble, labels, source, symbols};
+
+fn parse(text: &str) -> Vec<(u16, String)> {
+    symbols::parse(text).unwrap().into_iter().collect()
+}
+
+fn symbol(address: u16, name: &str) -> (u16, String) {
+    (address, name.to_string())
+}
+
+#[test]
+fn reads_the_common_formats() {
+    let simple = "; hand written\nPLAYER_X = 0x20c0\nVRAM EQU 2400H\nSTART: equ $0100\n";
+    assert_eq!(
+        parse(simple),
+        vec![
+            symbol(0x0100, "START"),
+            symbol(0x20c0, "PLAYER_X"),
+            symbol(0x2400, "VRAM")
+        ]
+    );
+    // Addresses are hex the way the listing prints them, but EQU numbers are
+    // read like the assembler's.
+    assert_eq!(
+        parse("SCORE = 20f8\nLIVES EQU 10\nMASK EQU 11B"),
+        vec![
+            symbol(3, "MASK"),
+            symbol(10, "LIVES"),
+            symbol(0x20f8, "SCORE")
+        ]
+    );
+    let zmac = "Symbol Table:\n\nloop            0105    start           0100\n";
+    assert_eq!(
+        parse(zmac),
+        vec![symbol(0x0100, "start"), symbol(0x0105, "loop")]
+    );
+    let sym = "0100 START   0105 LOOP\n0109' MSG\n";
+    assert_eq!(
+        parse(sym),
+        vec![
+            symbol(0x0100, "START"),
+            symbol(0x0105, "LOOP"),
+            symbol(0x0109, "MSG")
+        ]
+    );
+    let asl_listing = "  symbol table (* = unused):\n  ------------------------\n\
+        *ARCHITECTURE :  i8080 - | *CASESENSITIVE : 0 - |\n\
+        LOOP :  105 C |  START :  100 C |\n";
+    assert_eq!(
+        parse(asl_listing),
+        vec![symbol(0x0100, "START"), symbol(0x0105, "LOOP")]
+    );
+    let asl_map = "Segment CODE\nSymbols in Segment CODE\nSTART 100 4 0\nLOOP 105 4 0\n\nInfo\n";
+    assert_eq!(
+        parse(asl_map),
+        vec![symbol(0x0100, "START"), symbol(0x0105, "LOOP")]
+    );
+}
+
+#[test]
+fn reports_bad_lines() {
+    let error = |text: &str| symbols::parse(text).unwrap_err().to_string();
+    assert_eq!(error("\nSTART = 0x10000"), "line 2: Bad address 0x10000");
+    assert_eq!(
+        error("2START = 1"),
+        "line 1: 2START can't be used as a name"
+    );
+    assert_eq!(error("SP = 1"), "line 1: SP can't be used as a name");
+    // Either would be defined twice in the asm output.
+    assert_eq!(
+        error("LOOP = 10\nloop = 20"),
+        "line 2: loop is already 0010"
+    );
+    assert_eq!(
+        error("L_0010 = 20"),
+        "line 1: L_0010 is the name of the generated label for 0010"
+    );
+    assert_eq!(
+        error("SUB_0010 = 20"),
+        "line 1: SUB_0010 is the name of the generated label for 0010"
+    );
+    assert_eq!(
+        parse("L_0010 = 10\nLOOP = 20\nLOOP = 20"),
+        vec![symbol(0x10, "L_0010"), symbol(0x20, "LOOP")]
+    );
+}
+
+#[test]
+fn symbols_replace_labels_and_addresses() {
+    let program = [
+        0x21, 0x00, 0x24, // 0000: LXI H,2400H
+        0x11, 0x00, 0x00, // 0003: LXI D,0
+        0x3a, 0xc0, 0x20, // 0006: LDA 20C0H
+        0xcd, 0x0f, 0x00, // 0009: CALL 000F
+        0xc3, 0x06, 0x00, // 000c: JMP 0006
+        0xc9, // 000f: RET
+    ];
+    let lines = common::lines_from_bytes(&program, 0);
+    let mut labels = labels::generate(&lines);
+    for (address, name) in symbols::parse("VRAM = 0x2400\nPLAYER_X = 0x20c0\nMAIN = 6").unwrap() {
+        labels.insert_symbol(address, name).unwrap();
+    }
+    let mut text = Vec::new();
+    source::write(&mut text, &lines, &labels).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("PLAYER_X EQU     20C0H\n"), "{}", text);
+    assert!(text.contains("LXI     H,VRAM\n"), "{}", text);
+    assert!(
+        text.contains("MAIN:\n        LDA     PLAYER_X\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("JMP     MAIN\n"), "{}", text);
+    // The generated label is still used where there isn't a symbol.
+    assert!(text.contains("CALL    SUB_000F\n"), "{}", text);
+    assert_eq!(assemble(&text).unwrap().bytes, program);
+}
+
+#[test]
+fn the_first_name_for_an_address_wins() {
+    let lines = common::lines_from_bytes(
+        &[
+            0xcd, 0x06, 0x00, // 0000: CALL 0006
+            0xc3, 0x07, 0x00, // 0003: JMP 0007
+            0xc9, // 0006: RET
+            0xc9, // 0007: RET
+        ],
+        0,
+    );
+    let mut labels = labels::generate(&lines);
+    // A name someone chose wins over the generated one.
+    labels.insert_symbol(0x06, "PRINT".to_string()).unwrap();
+    assert_eq!(labels.get(0x06), Some("PRINT"));
+    assert_eq!(labels.get(0x07), Some("L_0007"));
+    assert_eq!(labels.symbol(0x07), None);
+    assert_eq!(labels.len(), 2);
+    // And over one given after it, as it would from a later symbol file.
+    labels.insert_symbol(0x06, "OUTPUT".to_string()).unwrap();
+    assert_eq!(labels.get(0x06), Some("PRINT"));
+    // But a name can't go on a second address, whatever its case.
+    assert_eq!(
+        labels.insert_symbol(0x07, "print".to_string()),
+        Err("print is already 0006".to_string())
+    );
+    assert_eq!(labels.get(0x07), Some("L_0007"));
+}
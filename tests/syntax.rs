@@ -0,0 +1,148 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::listing::DataFormat;
+use eightyeighty_disassembler::syntax::{Dialect, Hex};
+use eightyeighty_disassembler::{assemble, decode, labels, listing, source, Syntax};
+
+const PROGRAM: &str = "
+        ORG 100H
+START:  LXI D,0102H
+        MVI B,5
+        MOV A,M
+        JNZ START
+        LDA 0FE00H
+        OUT 3
+        RST 7
+        RZ
+";
+
+// Each instruction in PROGRAM, written out.
+fn instructions(syntax: &Syntax) -> Vec<String> {
+    let lines = common::lines(PROGRAM);
+    let labels = labels::generate(&lines);
+    lines
+        .iter()
+        .map(|line| match *line {
+            listing::Line::Instruction(ref instruction) => {
+                syntax.instruction(instruction, Some(&labels))
+            }
+            listing::Line::Data { .. } => panic!("{:?}", line),
+        })
+        .collect()
+}
+
+#[test]
+fn writes_numbers_in_each_style() {
+    let syntax = |hex, pad| Syntax {
+        hex,
+        pad,
+        ..Syntax::default()
+    };
+    let numbers = |syntax: Syntax| {
+        vec![
+            syntax.byte(0x5),
+            syntax.byte(0xfe),
+            syntax.word(0x0102),
+            syntax.word(0xfe00),
+        ]
+    };
+    assert_eq!(
+        numbers(syntax(Hex::Hash, false)),
+        vec!["#5", "#fe", "#102", "#fe00"]
+    );
+    assert_eq!(
+        numbers(syntax(Hex::C, true)),
+        vec!["0x05", "0xfe", "0x0102", "0xfe00"]
+    );
+    assert_eq!(
+        numbers(syntax(Hex::Intel, false)),
+        vec!["5H", "0FEH", "102H", "0FE00H"]
+    );
+    assert_eq!(
+        numbers(syntax(Hex::Dollar, true)),
+        vec!["$05", "$fe", "$0102", "$fe00"]
+    );
+    let lower = Syntax {
+        uppercase: false,
+        ..Syntax::assembler()
+    };
+    assert_eq!(lower.word(0xfe00), "0fe00h");
+}
+
+#[test]
+fn writes_intel_and_zilog() {
+    assert_eq!(
+        instructions(&Syntax::default()),
+        vec![
+            "LXI D,#102",
+            "MVI B,#5",
+            "MOV A,M",
+            "JNZ L_0100",
+            "LDA #fe00",
+            "OUT #3",
+            "RST 7",
+            "RZ"
+        ]
+    );
+    let zilog = Syntax {
+        dialect: Dialect::Zilog,
+        uppercase: false,
+        ..Syntax::assembler()
+    };
+    assert_eq!(
+        instructions(&zilog),
+        vec![
+            "ld de,0102h",
+            "ld b,05h",
+            "ld a,(hl)",
+            "jp nz,L_0100",
+            "ld a,(0fe00h)",
+            "out (03h),a",
+            "rst 38h",
+            "ret z"
+        ]
+    );
+    assert_eq!(
+        zilog.data(b"HI\0", DataFormat::Text, None),
+        "defm \"HI\",00h"
+    );
+    // Nothing is left in Intel mnemonics unless Zilog spells it the same.
+    for opcode in 0..=255u8 {
+        let instruction = decode(&[opcode, 0x34, 0x12], 0).unwrap();
+        let intel = Syntax::default().instruction(&instruction, None);
+        let zilog = Syntax {
+            dialect: Dialect::Zilog,
+            ..Syntax::default()
+        }
+        .instruction(&instruction, None);
+        let same = [
+            "NOP", "DAA", "PUSH", "POP", "CALL", "RET", "DI", "EI", "RST", "SUB",
+        ];
+        let mnemonic = intel.split(' ').next().unwrap();
+        assert!(
+            same.contains(&mnemonic) || intel != zilog,
+            "{:02x}: {}",
+            opcode,
+            intel
+        );
+    }
+}
+
+#[test]
+fn writes_source_in_lower_case() {
+    let lines = common::lines(PROGRAM);
+    let labels = labels::generate(&lines);
+    let lower = Syntax {
+        uppercase: false,
+        ..Syntax::assembler()
+    };
+    let mut text = Vec::new();
+    source::write_with_syntax(&mut text, &lines, &labels, &lower).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.starts_with("        org     0100h\n"), "{}", text);
+    assert!(text.contains("        lxi     d,0102h\n"), "{}", text);
+    // The assembler doesn't mind what case things are in.
+    assert_eq!(assemble(&text).unwrap(), assemble(PROGRAM).unwrap());
+}
@@ -0,0 +1,116 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::timing::timing;
+use eightyeighty_disassembler::{decode, Timing};
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+// T-states from Intel's 8080 manual, with what a taken conditional call or
+// return takes.
+const KNOWN: &[(u8, u8, Option<u8>)] = &[
+    (0x00, 4, None),      // NOP
+    (0x01, 10, None),     // LXI B
+    (0x03, 5, None),      // INX B
+    (0x04, 5, None),      // INR B
+    (0x06, 7, None),      // MVI B
+    (0x09, 10, None),     // DAD B
+    (0x22, 16, None),     // SHLD
+    (0x32, 13, None),     // STA
+    (0x34, 10, None),     // INR M
+    (0x36, 10, None),     // MVI M
+    (0x3a, 13, None),     // LDA
+    (0x41, 5, None),      // MOV B,C
+    (0x46, 7, None),      // MOV B,M
+    (0x70, 7, None),      // MOV M,B
+    (0x76, 7, None),      // HLT
+    (0x80, 4, None),      // ADD B
+    (0x86, 7, None),      // ADD M
+    (0xc0, 5, Some(11)),  // RNZ
+    (0xc1, 10, None),     // POP B
+    (0xc2, 10, None),     // JNZ, the same either way
+    (0xc3, 10, None),     // JMP
+    (0xc4, 11, Some(17)), // CNZ
+    (0xc5, 11, None),     // PUSH B
+    (0xc6, 7, None),      // ADI
+    (0xc7, 11, None),     // RST 0
+    (0xc9, 10, None),     // RET
+    (0xcd, 17, None),     // CALL
+    (0xd3, 10, None),     // OUT
+    (0xdb, 10, None),     // IN
+    (0xe3, 18, None),     // XTHL
+    (0xe9, 5, None),      // PCHL
+    (0xeb, 4, None),      // XCHG
+    (0xf3, 4, None),      // DI
+    (0xf9, 5, None),      // SPHL
+    (0xfc, 11, Some(17)), // CM
+    (0xf8, 5, Some(11)),  // RM
+];
+
+#[test]
+fn matches_the_manual() {
+    for &(opcode, cycles, taken) in KNOWN {
+        assert_eq!(timing(opcode), Timing { cycles, taken }, "{:02x}", opcode);
+    }
+    assert_eq!(timing(0xc4).most(), 17);
+    assert_eq!(timing(0xc3).most(), 10);
+    let instruction = decode(&[0xcd, 0x00, 0x10], 0).unwrap();
+    assert_eq!(instruction.timing(), timing(0xcd));
+}
+
+#[test]
+fn undocumented_opcodes_take_as_long_as_the_documented_ones() {
+    for opcode in 0..=255u8 {
+        let instruction = decode(&[opcode, 0, 0], 0).unwrap();
+        if !instruction.undocumented {
+            continue;
+        }
+        let documented = match opcode {
+            0xcb => 0xc3,
+            0xd9 => 0xc9,
+            0xdd | 0xed | 0xfd => 0xcd,
+            _ => 0x00,
+        };
+        assert_eq!(timing(opcode), timing(documented), "{:02x}", opcode);
+    }
+}
+
+#[test]
+fn only_conditional_calls_and_returns_depend_on_the_condition() {
+    for opcode in 0..=255u8 {
+        let conditional = opcode & 0xc7 == 0xc0 || opcode & 0xc7 == 0xc4;
+        assert_eq!(
+            timing(opcode).taken.is_some(),
+            conditional,
+            "{:02x}",
+            opcode
+        );
+    }
+}
+
+#[test]
+fn cycle_range_option() {
+    let path = env::temp_dir().join(format!("timing-{}.bin", process::id()));
+    // NOP, MVI A,1, RET.
+    fs::write(&path, [0x00, 0x3e, 0x01, 0xc9]).unwrap();
+    let run = |range: &str| {
+        Command::new(env!("CARGO_BIN_EXE_eightyeighty-disassembler"))
+            .args(["--cycle-range", range])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let forwards = run("0-3");
+    let backwards = run("3-0");
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(forwards.stdout).unwrap();
+    assert!(stdout.contains("0-3 takes 21 T-states\n"), "{}", stdout);
+    assert!(!backwards.status.success());
+    let stderr = String::from_utf8(backwards.stderr).unwrap();
+    assert!(
+        stderr.starts_with("3-0 ends before it starts\n"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,61 @@
+extern crate eightyeighty_disassembler;
+
+use eightyeighty_disassembler::listing::{self, DataFormat};
+use eightyeighty_disassembler::{trace, Image, Line};
+
+// JMP over three bytes of text, then a conditional jump and a call.
+const PROGRAM: &[u8] = &[
+    0xc3, 0x06, 0x00, // 0000: JMP 0006
+    b'A', b'B', b'C', // 0003: not code
+    0xca, 0x0c, 0x00, // 0006: JZ 000C
+    0xcd, 0x0d, 0x00, // 0009: CALL 000D
+    0x76, // 000c: HLT
+    0xc9, // 000d: RET
+    0x3e, // 000e: never reached, and a truncated MVI if it were
+];
+
+#[test]
+fn only_decodes_what_is_reached() {
+    let image = Image::from_binary(PROGRAM, 0).unwrap();
+    let instructions = trace::trace(&image, &[0]);
+    let addresses: Vec<u16> = instructions.keys().cloned().collect();
+    assert_eq!(addresses, vec![0x00, 0x06, 0x09, 0x0c, 0x0d]);
+    let lines = listing::traced(&image, &instructions);
+    let data = |address: u16, bytes: &[u8]| Line::Data {
+        address,
+        bytes: bytes.to_vec(),
+        format: DataFormat::Bytes,
+        warning: None,
+        comment: None,
+    };
+    assert_eq!(lines[1], data(0x03, b"ABC"));
+    assert_eq!(lines[lines.len() - 1], data(0x0e, &[0x3e]));
+    // Read straight through, the text turns into MOV instructions.
+    assert!(matches!(listing::linear(&image)[1], Line::Instruction(_)));
+}
+
+#[test]
+fn stops_at_the_edges_of_the_image() {
+    // A jump out of the image and a call whose operand is cut off.
+    let image = Image::from_binary(&[0xc2, 0x00, 0x80, 0xcd, 0x00], 0x100).unwrap();
+    let instructions = trace::trace(&image, &[0x100, 0x4000]);
+    let addresses: Vec<u16> = instructions.keys().cloned().collect();
+    assert_eq!(addresses, vec![0x100]);
+}
+
+#[test]
+fn keeps_the_first_of_two_overlapping_paths() {
+    // Jumping into the middle of the LXI reads its operand as a NOP.
+    let image = Image::from_binary(&[0x21, 0x00, 0x00, 0xc9], 0).unwrap();
+    let instructions = trace::trace(&image, &[0, 2]);
+    let addresses: Vec<u16> = instructions.keys().cloned().collect();
+    assert_eq!(addresses, vec![0, 3]);
+}
+
+#[test]
+fn starts_from_the_origin_and_the_rst_vectors() {
+    let image = Image::from_binary(&[0; 0x20], 0).unwrap();
+    assert_eq!(trace::default_entry_points(&image), vec![0, 8, 0x10, 0x18]);
+    let image = Image::from_binary(&[0; 0x20], 0x100).unwrap();
+    assert_eq!(trace::default_entry_points(&image), vec![0x100]);
+}
@@ -0,0 +1,80 @@
+extern crate eightyeighty_disassembler;
+
+mod common;
+
+use eightyeighty_disassembler::xref::{self, Access, Reference};
+use eightyeighty_disassembler::Line;
+
+fn references(lines: &[Line]) -> Vec<(u16, Vec<Reference>)> {
+    xref::references(lines).into_iter().collect()
+}
+
+fn reference(access: Access, from: u16) -> Reference {
+    Reference { access, from }
+}
+
+#[test]
+fn finds_reads_writes_jumps_and_calls() {
+    let found = references(&common::lines(
+        "
+        ORG 0
+START:  LDA 40H
+        STA 41H
+        LHLD 42H
+        SHLD 42H
+        CALL SUB
+        CZ SUB
+        JNZ START
+SUB:    LXI H,DONE
+        PCHL
+DONE:   RET
+",
+    ));
+    assert_eq!(
+        found,
+        vec![
+            (0x00, vec![reference(Access::Jump, 0x12)]),
+            (
+                0x15,
+                vec![reference(Access::Call, 0x0c), reference(Access::Call, 0x0f)]
+            ),
+            (0x19, vec![reference(Access::Jump, 0x18)]),
+            (0x40, vec![reference(Access::Read, 0x00)]),
+            (0x41, vec![reference(Access::Write, 0x03)]),
+            (
+                0x42,
+                vec![
+                    reference(Access::Read, 0x06),
+                    reference(Access::Write, 0x09)
+                ]
+            ),
+        ]
+    );
+    assert_eq!(xref::describe(&found[5].1), "read by 6; written by 9");
+}
+
+#[test]
+fn follows_lxi_into_ldax_and_stax() {
+    let found = references(&common::lines_from_bytes(
+        &[
+            0x01, 0x40, 0x00, // 0000: LXI B,40H
+            0x11, 0x41, 0x00, // 0003: LXI D,41H
+            0x0a, // 0006: LDAX B
+            0x12, // 0007: STAX D
+            0x13, // 0008: INX D
+            0x12, // 0009: STAX D
+            0x0e, 0x00, // 000a: MVI C,0
+            0x0a, // 000c: LDAX B
+            0xc9, // 000d: RET
+        ],
+        0,
+    ));
+    // Once D and C change, the LDAX and STAX after could be anywhere.
+    assert_eq!(
+        found,
+        vec![
+            (0x40, vec![reference(Access::Read, 0x06)]),
+            (0x41, vec![reference(Access::Write, 0x07)]),
+        ]
+    );
+}